@@ -0,0 +1,138 @@
+use anyhow::{anyhow, Result, Error};
+use crate::table::{IndexedTable, Table};
+
+#[derive(Debug, PartialEq, Default)]
+pub struct Database {
+    pub(crate) tables: Vec<NamedTable>
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct NamedTable {
+    pub(crate) name: String,
+    pub(crate) table: Table
+}
+
+#[derive(Debug, PartialEq)]
+pub struct IndexedDatabase<'a> {
+    pub(crate) tables: Vec<NamedIndexedTable<'a>>
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct NamedIndexedTable<'a> {
+    pub(crate) name: String,
+    pub(crate) table: IndexedTable<'a>
+}
+
+impl Database {
+
+    pub fn new() -> Database {
+        Database {
+            tables: Vec::new()
+        }
+    }
+
+    pub fn add_table(&mut self, name: &str, table: Table) -> Result<(), Error> {
+        if self.tables.iter().any(|named_table| named_table.name == name) {
+            Err(anyhow!("Table {} already exists in the database", name))
+        } else {
+            self.tables.push(NamedTable {
+                name: name.to_string(),
+                table
+            });
+            Ok(())
+        }
+    }
+
+    pub fn table_names(&self) -> Vec<&str> {
+        self.tables.iter().map(|named_table| named_table.name.as_str()).collect()
+    }
+
+    pub fn build_indices(&self) -> Result<IndexedDatabase<'_>, Error> {
+        let mut tables: Vec<NamedIndexedTable> = Vec::new();
+        for named_table in self.tables.iter() {
+            tables.push(NamedIndexedTable {
+                name: named_table.name.to_string(),
+                table: named_table.table.build_indices()?
+            });
+        }
+        Ok(IndexedDatabase {
+            tables
+        })
+    }
+}
+
+impl IndexedDatabase<'_> {
+
+    // Without a table name the database is expected to contain a single table, so that
+    // the queries against a single loaded CSV file do not have to name it
+    pub(crate) fn find_table(&self, table_name: Option<&str>) -> Result<&IndexedTable<'_>, Error> {
+        match table_name {
+            Some(table_name) => self.tables.iter()
+                .find(|named_table| named_table.name == table_name)
+                .map(|named_table| &named_table.table)
+                .ok_or_else(|| anyhow!("Cannot find table {}, it does not exist in the database, existing tables {}",
+                    table_name,
+                    self.table_names().join(", "))
+                ),
+            None => match self.tables.as_slice() {
+                [named_table] => Ok(&named_table.table),
+                _ => Err(anyhow!("Table to query has to be specified with FROM, existing tables {}",
+                    self.table_names().join(", ")))
+            }
+        }
+    }
+
+    fn table_names(&self) -> Vec<&str> {
+        self.tables.iter().map(|named_table| named_table.name.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use csv::ReaderBuilder;
+    use std::io::Cursor;
+
+    fn load_table(input: &str) -> Table {
+        let mut reader = ReaderBuilder::new().from_reader(Cursor::new(input));
+        Table::load_from(&mut reader).unwrap()
+    }
+
+    #[test]
+    fn should_find_tables_by_name() {
+        let mut database = Database::new();
+        database.add_table("cities", load_table("city\nBerlin")).unwrap();
+        database.add_table("countries", load_table("country\nGermany")).unwrap();
+        assert_eq!(database.table_names(), vec!["cities", "countries"]);
+        let indexed_database = database.build_indices().unwrap();
+        let countries = indexed_database.find_table(Some("countries")).unwrap();
+        assert_eq!(countries.underlying, &database.tables[1].table);
+        match indexed_database.find_table(Some("languages")) {
+            Err(e) => assert_eq!(e.to_string(), "Cannot find table languages, it does not exist in the database, existing tables cities, countries"),
+            Ok(_) => panic!("Error expected")
+        }
+        match indexed_database.find_table(None) {
+            Err(e) => assert_eq!(e.to_string(), "Table to query has to be specified with FROM, existing tables cities, countries"),
+            Ok(_) => panic!("Error expected")
+        }
+    }
+
+    #[test]
+    fn should_use_the_only_table_when_table_name_is_not_given() {
+        let mut database = Database::new();
+        database.add_table("cities", load_table("city\nBerlin")).unwrap();
+        let indexed_database = database.build_indices().unwrap();
+        let cities = indexed_database.find_table(None).unwrap();
+        assert_eq!(cities.underlying, &database.tables[0].table);
+    }
+
+    #[test]
+    fn should_produce_error_when_adding_table_with_existing_name() {
+        let mut database = Database::new();
+        database.add_table("cities", load_table("city\nBerlin")).unwrap();
+        match database.add_table("cities", load_table("city\nMadrid")) {
+            Err(e) => assert_eq!(e.to_string(), "Table cities already exists in the database"),
+            Ok(_) => panic!("Error expected")
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use std::fmt;
+use anyhow::{anyhow, Error, Result};
+use chrono::{DateTime, Datelike, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike};
+
+const MICROSECONDS_PER_SECOND: i64 = 1_000_000;
+
+const MICROSECONDS_PER_DAY: i64 = 86_400 * MICROSECONDS_PER_SECOND;
+
+// The units of DATE_TRUNC and DATE_DIFF
+const UNITS: [&str; 8] = ["year", "quarter", "month", "week", "day", "hour", "minute", "second"];
+
+// The fields of a date or a timestamp which can be extracted with EXTRACT
+pub(crate) const FIELDS: [&str; 11] = ["YEAR", "QUARTER", "MONTH", "WEEK", "DAY", "DOW", "DOY", "HOUR", "MINUTE", "SECOND", "EPOCH"];
+
+// Months and days vary in length (a month has 28 to 31 days, a day in a calendar has 23 to 25 hours),
+// so they are kept apart from the time and only resolved when the interval is added to a date
+#[derive(Debug, Clone, Copy)]
+pub struct Interval {
+    pub months: i64,
+    pub days: i64,
+    pub microseconds: i64
+}
+
+impl Interval {
+    // A sequence of amounts followed by their units, such as "1 year 2 months 3 days", optionally
+    // ending with the time as "hh:mm:ss", the units can be abbreviated and given in the singular
+    pub(crate) fn parse(input: &str) -> Option<Interval> {
+        let mut interval = Interval { months: 0, days: 0, microseconds: 0 };
+        let mut words = input.split_whitespace();
+        while let Some(word) = words.next() {
+            if word.contains(':') {
+                interval.microseconds = interval.microseconds.checked_add(parse_time_of_interval(word)?)?;
+                continue;
+            }
+            let amount: i64 = word.parse().ok()?;
+            let unit = words.next()?.to_lowercase();
+            let (months, days, microseconds) = match unit.trim_end_matches('s') {
+                "year" => (amount.checked_mul(12)?, 0, 0),
+                "mon" | "month" => (amount, 0, 0),
+                "week" => (0, amount.checked_mul(7)?, 0),
+                "day" => (0, amount, 0),
+                "hour" => (0, 0, amount.checked_mul(3600 * MICROSECONDS_PER_SECOND)?),
+                "min" | "minute" => (0, 0, amount.checked_mul(60 * MICROSECONDS_PER_SECOND)?),
+                "sec" | "second" => (0, 0, amount.checked_mul(MICROSECONDS_PER_SECOND)?),
+                _ => return None
+            };
+            interval = interval.checked_add(&Interval { months, days, microseconds })?;
+        }
+        Some(interval)
+    }
+
+    pub(crate) fn checked_add(&self, other: &Interval) -> Option<Interval> {
+        Some(Interval {
+            months: self.months.checked_add(other.months)?,
+            days: self.days.checked_add(other.days)?,
+            microseconds: self.microseconds.checked_add(other.microseconds)?
+        })
+    }
+
+    pub(crate) fn checked_neg(&self) -> Option<Interval> {
+        Some(Interval {
+            months: self.months.checked_neg()?,
+            days: self.days.checked_neg()?,
+            microseconds: self.microseconds.checked_neg()?
+        })
+    }
+
+    pub(crate) fn has_time(&self) -> bool {
+        self.microseconds != 0
+    }
+
+    // Intervals are compared assuming that a month has 30 days, like in SQL
+    pub(crate) fn length_in_microseconds(&self) -> i128 {
+        (self.months as i128 * 30 + self.days as i128) * MICROSECONDS_PER_DAY as i128 + self.microseconds as i128
+    }
+}
+
+// "[-]hh:mm[:ss[.ffffff]]"
+fn parse_time_of_interval(input: &str) -> Option<i64> {
+    let (sign, input) = match input.strip_prefix('-') {
+        Some(input) => (-1, input),
+        None => (1, input)
+    };
+    let parts: Vec<&str> = input.split(':').collect();
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [hours, minutes] => (hours, minutes, &"0"),
+        [hours, minutes, seconds] => (hours, minutes, seconds),
+        _ => return None
+    };
+    let (seconds, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+    if fraction.len() > 6 || !fraction.chars().all(|char| char.is_ascii_digit()) {
+        return None;
+    }
+    let fraction_microseconds: i64 = format!("{:0<6}", fraction).parse().ok()?;
+    let whole_seconds = hours.parse::<i64>().ok()?.checked_mul(3600)?
+        .checked_add(minutes.parse::<i64>().ok()?.checked_mul(60)?)?
+        .checked_add(seconds.parse::<i64>().ok()?)?;
+    Some(sign * whole_seconds.checked_mul(MICROSECONDS_PER_SECOND)?.checked_add(fraction_microseconds)?)
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let plural = |amount: i64| if amount.abs() == 1 { "" } else { "s" };
+        let mut parts: Vec<String> = Vec::new();
+        if self.months != 0 {
+            parts.push(format!("{} month{}", self.months, plural(self.months)));
+        }
+        if self.days != 0 {
+            parts.push(format!("{} day{}", self.days, plural(self.days)));
+        }
+        if self.microseconds != 0 || parts.is_empty() {
+            let sign = if self.microseconds < 0 { "-" } else { "" };
+            let microseconds = self.microseconds.unsigned_abs();
+            let seconds = microseconds / MICROSECONDS_PER_SECOND as u64;
+            let mut time = format!("{}{:02}:{:02}:{:02}", sign, seconds / 3600, seconds / 60 % 60, seconds % 60);
+            let fraction = microseconds % MICROSECONDS_PER_SECOND as u64;
+            if fraction != 0 {
+                time.push_str(format!(".{:06}", fraction).trim_end_matches('0'));
+            }
+            parts.push(time);
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+// Only the ISO 8601 format "yyyy-mm-dd" is recognized
+pub(crate) fn parse_date(input: &str) -> Option<NaiveDate> {
+    if input.len() != 10 {
+        return None;
+    }
+    NaiveDate::parse_from_str(input, "%Y-%m-%d").ok()
+}
+
+// The date and the time are separated with "T" or a space, a timestamp with a time zone
+// offset (such as "2024-01-31T10:00:00Z") is converted to UTC
+pub(crate) fn parse_timestamp(input: &str) -> Option<NaiveDateTime> {
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"].iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+        .or_else(|| DateTime::parse_from_rfc3339(input).ok().map(|timestamp| timestamp.naive_utc()))
+}
+
+// A timestamp can also be given only by its date, standing for the midnight of the day
+pub(crate) fn parse_timestamp_or_date(input: &str) -> Option<NaiveDateTime> {
+    parse_timestamp(input).or_else(|| parse_date(input).map(|date| date.and_time(NaiveTime::MIN)))
+}
+
+pub(crate) fn format_timestamp(timestamp: &NaiveDateTime) -> String {
+    timestamp.format("%Y-%m-%d %H:%M:%S%.f").to_string()
+}
+
+pub(crate) fn add_interval(timestamp: NaiveDateTime, interval: &Interval) -> Option<NaiveDateTime> {
+    add_months(timestamp, interval.months)?
+        .checked_add_signed(TimeDelta::try_days(interval.days)?)?
+        .checked_add_signed(TimeDelta::microseconds(interval.microseconds))
+}
+
+// Adding a month to the last days of a month produces the last day of the next month
+fn add_months(timestamp: NaiveDateTime, months: i64) -> Option<NaiveDateTime> {
+    let months_to_add = Months::new(u32::try_from(months.unsigned_abs()).ok()?);
+    if months >= 0 {
+        timestamp.checked_add_months(months_to_add)
+    } else {
+        timestamp.checked_sub_months(months_to_add)
+    }
+}
+
+// The difference is expressed in days and the remaining time
+pub(crate) fn interval_between(start: NaiveDateTime, end: NaiveDateTime) -> Option<Interval> {
+    let difference = end.signed_duration_since(start);
+    let days = difference.num_days();
+    Some(Interval {
+        months: 0,
+        days,
+        microseconds: (difference - TimeDelta::try_days(days)?).num_microseconds()?
+    })
+}
+
+fn unknown_unit_error(unit: &str) -> Error {
+    anyhow!("Unknown unit '{}', expected one of {}", unit, UNITS.join(", "))
+}
+
+// Weeks start on Monday
+pub(crate) fn truncate(timestamp: NaiveDateTime, unit: &str) -> Result<NaiveDateTime, Error> {
+    let date = timestamp.date();
+    let time = timestamp.time();
+    let truncated = match unit.to_lowercase().as_str() {
+        "year" => date.with_day(1).and_then(|date| date.with_month(1)).map(|date| date.and_time(NaiveTime::MIN)),
+        "quarter" => date.with_day(1).and_then(|date| date.with_month(date.month0() / 3 * 3 + 1)).map(|date| date.and_time(NaiveTime::MIN)),
+        "month" => date.with_day(1).map(|date| date.and_time(NaiveTime::MIN)),
+        "week" => date.checked_sub_days(chrono::Days::new(date.weekday().num_days_from_monday() as u64)).map(|date| date.and_time(NaiveTime::MIN)),
+        "day" => Some(date.and_time(NaiveTime::MIN)),
+        "hour" => NaiveTime::from_hms_opt(time.hour(), 0, 0).map(|time| date.and_time(time)),
+        "minute" => NaiveTime::from_hms_opt(time.hour(), time.minute(), 0).map(|time| date.and_time(time)),
+        "second" => NaiveTime::from_hms_opt(time.hour(), time.minute(), time.second()).map(|time| date.and_time(time)),
+        _ => return Err(unknown_unit_error(unit))
+    };
+    truncated.ok_or_else(|| anyhow!("Cannot truncate {} to {}", format_timestamp(&timestamp), unit))
+}
+
+// The week is the ISO 8601 week of the year, the day of the week (DOW) is 0 for Sunday,
+// the epoch is the number of seconds since 1970-01-01 00:00:00
+pub(crate) fn extract(timestamp: NaiveDateTime, field: &str) -> Result<i64, Error> {
+    Ok(match field {
+        "YEAR" => timestamp.year() as i64,
+        "QUARTER" => (timestamp.month0() / 3 + 1) as i64,
+        "MONTH" => timestamp.month() as i64,
+        "WEEK" => timestamp.iso_week().week() as i64,
+        "DAY" => timestamp.day() as i64,
+        "DOW" => timestamp.weekday().num_days_from_sunday() as i64,
+        "DOY" => timestamp.ordinal() as i64,
+        "HOUR" => timestamp.hour() as i64,
+        "MINUTE" => timestamp.minute() as i64,
+        "SECOND" => timestamp.second() as i64,
+        "EPOCH" => timestamp.and_utc().timestamp(),
+        _ => return Err(anyhow!("Unknown field {} of a date, expected one of {}", field, FIELDS.join(", ")))
+    })
+}
+
+// The number of whole units from the start to the end, negative when the end precedes the start
+pub(crate) fn difference(unit: &str, start: NaiveDateTime, end: NaiveDateTime) -> Result<i64, Error> {
+    let elapsed = end.signed_duration_since(start);
+    let months = || {
+        let mut months = (end.year() as i64 * 12 + end.month0() as i64) - (start.year() as i64 * 12 + start.month0() as i64);
+        let is_month_incomplete = |months: i64| add_months(start, months).is_none_or(|reached| if months > 0 { reached > end } else { reached < end });
+        if months != 0 && is_month_incomplete(months) {
+            months -= months.signum();
+        }
+        months
+    };
+    Ok(match unit.to_lowercase().as_str() {
+        "year" => months() / 12,
+        "quarter" => months() / 3,
+        "month" => months(),
+        "week" => elapsed.num_weeks(),
+        "day" => elapsed.num_days(),
+        "hour" => elapsed.num_hours(),
+        "minute" => elapsed.num_minutes(),
+        "second" => elapsed.num_seconds(),
+        _ => return Err(unknown_unit_error(unit))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn timestamp(input: &str) -> NaiveDateTime {
+        parse_timestamp(input).unwrap()
+    }
+
+    #[test]
+    fn should_parse_dates_and_timestamps() {
+        assert_eq!(parse_date("2024-02-29"), NaiveDate::from_ymd_opt(2024, 2, 29));
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("2024-2-9"), None);
+        assert_eq!(parse_date("Berlin"), None);
+        assert_eq!(format_timestamp(&timestamp("2024-01-31T10:15:00")), "2024-01-31 10:15:00");
+        assert_eq!(format_timestamp(&timestamp("2024-01-31 10:15:30.25")), "2024-01-31 10:15:30.250");
+        assert_eq!(format_timestamp(&timestamp("2024-01-31T10:15")), "2024-01-31 10:15:00");
+        assert_eq!(format_timestamp(&timestamp("2024-01-31T10:15:00+02:00")), "2024-01-31 08:15:00");
+        assert_eq!(parse_timestamp("2024-01-31"), None);
+        assert_eq!(parse_timestamp("2024-01-31 25:00:00"), None);
+    }
+
+    #[test]
+    fn should_parse_and_format_intervals() {
+        let expected_intervals = vec![
+            ("3 days", "3 days"),
+            ("1 year 2 months", "14 months"),
+            ("2 weeks 1 day", "15 days"),
+            ("1 day 2 hours 30 minutes", "1 day 02:30:00"),
+            ("-1 month 90 sec", "-1 month 00:01:30"),
+            ("1 mon 01:02:03.5", "1 month 01:02:03.5"),
+            ("-00:30", "-00:30:00"),
+            ("", "00:00:00")
+        ];
+        for (input, expected) in expected_intervals.into_iter() {
+            let interval = Interval::parse(input).unwrap();
+            assert_eq!(interval.to_string(), expected, "{}", input);
+            assert_eq!(Interval::parse(&interval.to_string()).unwrap().length_in_microseconds(), interval.length_in_microseconds());
+        }
+        assert!(Interval::parse("3 fortnights").is_none());
+        assert!(Interval::parse("3").is_none());
+        assert!(Interval::parse("9223372036854775807 years").is_none());
+    }
+
+    #[test]
+    fn should_add_intervals_to_timestamps() {
+        let interval = |input: &str| Interval::parse(input).unwrap();
+        assert_eq!(add_interval(timestamp("2024-01-31 10:00"), &interval("1 month")), Some(timestamp("2024-02-29 10:00")));
+        assert_eq!(add_interval(timestamp("2024-03-31 10:00"), &interval("-1 month 1 day")), Some(timestamp("2024-03-01 10:00")));
+        assert_eq!(add_interval(timestamp("2024-12-31 23:30"), &interval("45 minutes")), Some(timestamp("2025-01-01 00:15")));
+        assert_eq!(add_interval(timestamp("2024-01-01 00:00"), &interval("1000000 years")), None);
+        assert_eq!(interval_between(timestamp("2024-01-01 12:00"), timestamp("2024-01-03 06:00")).unwrap().to_string(), "1 day 18:00:00");
+        assert_eq!(interval_between(timestamp("2024-01-03 06:00"), timestamp("2024-01-01 12:00")).unwrap().to_string(), "-1 day -18:00:00");
+    }
+
+    #[test]
+    fn should_truncate_extract_and_compute_differences() {
+        let moment = timestamp("2024-05-16 13:45:30");
+        let expected_truncations = vec![
+            ("year", "2024-01-01 00:00:00"),
+            ("quarter", "2024-04-01 00:00:00"),
+            ("MONTH", "2024-05-01 00:00:00"),
+            ("week", "2024-05-13 00:00:00"),
+            ("day", "2024-05-16 00:00:00"),
+            ("hour", "2024-05-16 13:00:00"),
+            ("minute", "2024-05-16 13:45:00"),
+            ("second", "2024-05-16 13:45:30")
+        ];
+        for (unit, expected) in expected_truncations.into_iter() {
+            assert_eq!(format_timestamp(&truncate(moment, unit).unwrap()), expected, "{}", unit);
+        }
+        assert_eq!(truncate(moment, "fortnight").unwrap_err().to_string(),
+            "Unknown unit 'fortnight', expected one of year, quarter, month, week, day, hour, minute, second");
+        let extracted: Vec<i64> = FIELDS.iter().map(|field| extract(moment, field).unwrap()).collect();
+        assert_eq!(extracted, vec![2024, 2, 5, 20, 16, 4, 137, 13, 45, 30, 1715867130]);
+        let expected_differences = vec![
+            ("year", "2023-05-17 00:00", 0),
+            ("year", "2023-05-16 00:00", 1),
+            ("month", "2024-01-31 00:00", 3),
+            ("month", "2024-07-15 00:00", -1),
+            ("quarter", "2023-11-16 00:00", 2),
+            ("week", "2024-05-01 00:00", 2),
+            ("day", "2024-05-17 12:00", 0),
+            ("day", "2024-05-14 12:00", 2),
+            ("hour", "2024-05-16 00:00", 13),
+            ("minute", "2024-05-16 14:00", -14),
+            ("second", "2024-05-16 13:45", 30)
+        ];
+        for (unit, start, expected) in expected_differences.into_iter() {
+            assert_eq!(difference(unit, timestamp(start), moment).unwrap(), expected, "{} from {}", unit, start);
+        }
+    }
+}
@@ -0,0 +1,515 @@
+use std::fmt;
+use anyhow::{anyhow, Error, Result};
+use crate::date_time;
+use crate::table::ColumnType;
+use crate::value::{arithmetic_error, Value};
+
+// The built-in scalar functions, each of them is described by a signature which is used to check
+// the number of the arguments when the query is parsed and their types before it is executed
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Function {
+    Upper,
+    Lower,
+    Trim,
+    Length,
+    Substr,
+    Replace,
+    Concat,
+    SplitPart,
+    Lpad,
+    Rpad,
+    StartsWith,
+    Round,
+    Abs,
+    Floor,
+    Ceil,
+    Power,
+    Ln,
+    Mod,
+    DateTrunc,
+    Extract,
+    DateDiff
+}
+
+const FUNCTIONS: [(&str, Function); 21] = [
+    ("UPPER", Function::Upper),
+    ("LOWER", Function::Lower),
+    ("TRIM", Function::Trim),
+    ("LENGTH", Function::Length),
+    ("SUBSTR", Function::Substr),
+    ("REPLACE", Function::Replace),
+    ("CONCAT", Function::Concat),
+    ("SPLIT_PART", Function::SplitPart),
+    ("LPAD", Function::Lpad),
+    ("RPAD", Function::Rpad),
+    ("STARTS_WITH", Function::StartsWith),
+    ("ROUND", Function::Round),
+    ("ABS", Function::Abs),
+    ("FLOOR", Function::Floor),
+    ("CEIL", Function::Ceil),
+    ("POWER", Function::Power),
+    ("LN", Function::Ln),
+    ("MOD", Function::Mod),
+    ("DATE_TRUNC", Function::DateTrunc),
+    ("EXTRACT", Function::Extract),
+    ("DATE_DIFF", Function::DateDiff)
+];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum ParameterType {
+    Text,
+    Integer,
+    Numeric,
+    Temporal,
+    Any
+}
+
+impl fmt::Display for ParameterType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ParameterType::Text => "text",
+            ParameterType::Integer => "integer",
+            ParameterType::Numeric => "numeric",
+            ParameterType::Temporal => "date or timestamp",
+            ParameterType::Any => "any"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+enum ResultType {
+    Of(ColumnType),
+    // An integer when all the numeric arguments are integers and a float otherwise
+    Numeric,
+    // The type of the argument at the given position
+    Argument(usize)
+}
+
+// The arguments past the required ones are optional, a variadic function accepts any number
+// of further arguments of the type of its last parameter
+struct Signature {
+    parameters: &'static [ParameterType],
+    required: usize,
+    variadic: bool,
+    result: ResultType
+}
+
+impl Function {
+    pub(crate) fn from(name: &str) -> Option<Function> {
+        FUNCTIONS.iter()
+            .find(|(function_name, _)| *function_name == name)
+            .map(|(_, function)| *function)
+    }
+
+    fn signature(&self) -> Signature {
+        let (parameters, required, variadic, result): (&'static [ParameterType], usize, bool, ResultType) = match self {
+            Function::Upper | Function::Lower | Function::Trim =>
+                (&[ParameterType::Text], 1, false, ResultType::Of(ColumnType::Text)),
+            Function::Length =>
+                (&[ParameterType::Text], 1, false, ResultType::Of(ColumnType::Integer)),
+            Function::Substr =>
+                (&[ParameterType::Text, ParameterType::Integer, ParameterType::Integer], 2, false, ResultType::Of(ColumnType::Text)),
+            Function::Replace =>
+                (&[ParameterType::Text, ParameterType::Text, ParameterType::Text], 3, false, ResultType::Of(ColumnType::Text)),
+            Function::Concat =>
+                (&[ParameterType::Any], 1, true, ResultType::Of(ColumnType::Text)),
+            Function::SplitPart =>
+                (&[ParameterType::Text, ParameterType::Text, ParameterType::Integer], 3, false, ResultType::Of(ColumnType::Text)),
+            Function::Lpad | Function::Rpad =>
+                (&[ParameterType::Text, ParameterType::Integer, ParameterType::Text], 2, false, ResultType::Of(ColumnType::Text)),
+            Function::StartsWith =>
+                (&[ParameterType::Text, ParameterType::Text], 2, false, ResultType::Of(ColumnType::Integer)),
+            Function::Round =>
+                (&[ParameterType::Numeric, ParameterType::Integer], 1, false, ResultType::Numeric),
+            Function::Abs | Function::Floor | Function::Ceil =>
+                (&[ParameterType::Numeric], 1, false, ResultType::Numeric),
+            Function::Power =>
+                (&[ParameterType::Numeric, ParameterType::Numeric], 2, false, ResultType::Of(ColumnType::Float)),
+            Function::Ln =>
+                (&[ParameterType::Numeric], 1, false, ResultType::Of(ColumnType::Float)),
+            Function::Mod =>
+                (&[ParameterType::Numeric, ParameterType::Numeric], 2, false, ResultType::Numeric),
+            Function::DateTrunc =>
+                (&[ParameterType::Text, ParameterType::Temporal], 2, false, ResultType::Argument(1)),
+            Function::Extract =>
+                (&[ParameterType::Text, ParameterType::Temporal], 2, false, ResultType::Of(ColumnType::Integer)),
+            Function::DateDiff =>
+                (&[ParameterType::Text, ParameterType::Temporal, ParameterType::Temporal], 3, false, ResultType::Of(ColumnType::Integer))
+        };
+        Signature { parameters, required, variadic, result }
+    }
+
+    // A condition produces 1 when it is true and 0 when it is false, so that it can be used on its own in a filter
+    pub(crate) fn is_condition(&self) -> bool {
+        matches!(self, Function::StartsWith)
+    }
+
+    pub(crate) fn check_arity(&self, argument_count: usize) -> Result<(), String> {
+        let signature = self.signature();
+        let maximum = signature.parameters.len();
+        if argument_count >= signature.required && (signature.variadic || argument_count <= maximum) {
+            return Ok(());
+        }
+        let expected = if signature.variadic {
+            format!("at least {}", signature.required)
+        } else if signature.required == maximum {
+            signature.required.to_string()
+        } else {
+            format!("{} to {}", signature.required, maximum)
+        };
+        Err(format!("function {} expects {} argument(s), found {}", self, expected, argument_count))
+    }
+
+    // The type of an argument is unknown (None) for NULL or when it cannot be inferred from the
+    // types of the columns, such an argument is only checked when the function is applied
+    pub(crate) fn check_argument_types(&self, argument_types: &[Option<ColumnType>]) -> Result<Option<ColumnType>, Error> {
+        let signature = self.signature();
+        let mut numeric_argument_types: Vec<&Option<ColumnType>> = Vec::new();
+        for (position, argument_type) in argument_types.iter().enumerate() {
+            let parameter = signature.parameters.get(position)
+                .or(signature.parameters.last())
+                .unwrap_or(&ParameterType::Any);
+            let is_accepted = match (parameter, argument_type) {
+                (ParameterType::Any, _) | (_, None) => true,
+                (ParameterType::Text, Some(argument_type)) => *argument_type == ColumnType::Text,
+                (ParameterType::Integer, Some(argument_type)) => *argument_type == ColumnType::Integer,
+                (ParameterType::Numeric, Some(argument_type)) => matches!(argument_type, ColumnType::Integer | ColumnType::Float),
+                (ParameterType::Temporal, Some(argument_type)) => matches!(argument_type, ColumnType::Date | ColumnType::Timestamp)
+            };
+            if let (false, Some(argument_type)) = (is_accepted, argument_type) {
+                return Err(anyhow!("Function {} expects {} as argument {}, found {}", self, parameter, position + 1, argument_type));
+            }
+            if *parameter == ParameterType::Numeric {
+                numeric_argument_types.push(argument_type);
+            }
+        }
+        Ok(match signature.result {
+            ResultType::Of(result) => Some(result),
+            ResultType::Argument(position) => argument_types.get(position).cloned().flatten(),
+            ResultType::Numeric if numeric_argument_types.iter().any(|argument_type| argument_type.is_none()) => None,
+            ResultType::Numeric if numeric_argument_types.iter().all(|argument_type| **argument_type == Some(ColumnType::Integer)) => Some(ColumnType::Integer),
+            ResultType::Numeric => Some(ColumnType::Float)
+        })
+    }
+
+    // A function applied to NULL produces NULL, except CONCAT which skips the NULL arguments.
+    // The text arguments are taken as the text of any value, as text columns may contain numbers.
+    pub(crate) fn apply(&self, arguments: &[Value]) -> Result<Value, Error> {
+        if *self == Function::Concat {
+            return Ok(Value::Text(arguments.iter()
+                .filter(|argument| !argument.is_null())
+                .map(|argument| argument.to_string())
+                .collect()));
+        }
+        if arguments.iter().any(|argument| argument.is_null()) {
+            return Ok(Value::Null);
+        }
+        let text = |position: usize| arguments[position].to_string();
+        let integer = |position: usize| match &arguments[position] {
+            Value::Integer(value) => Ok(*value),
+            value => Err(anyhow!("Function {} expects integer as argument {}, found {}", self, position + 1, value))
+        };
+        let number = |position: usize| match &arguments[position] {
+            value if value.is_numeric() => Ok(value),
+            value => Err(anyhow!("Function {} expects numeric as argument {}, found {}", self, position + 1, value))
+        };
+        let temporal = |position: usize| arguments[position].as_timestamp()
+            .ok_or_else(|| anyhow!("Function {} expects date or timestamp as argument {}, found {}", self, position + 1, arguments[position]));
+        let has_argument = |position: usize| position < arguments.len();
+        Ok(match self {
+            Function::Upper => Value::Text(text(0).to_uppercase()),
+            Function::Lower => Value::Text(text(0).to_lowercase()),
+            Function::Trim => Value::Text(text(0).trim().to_string()),
+            Function::Length => Value::Integer(text(0).chars().count() as i64),
+            Function::Substr => {
+                let length = if has_argument(2) { Some(integer(2)?) } else { None };
+                Value::Text(substring(&text(0), integer(1)?, length)?)
+            },
+            Function::Replace => {
+                let searched = text(1);
+                if searched.is_empty() {
+                    Value::Text(text(0))
+                } else {
+                    Value::Text(text(0).replace(&searched, &text(2)))
+                }
+            },
+            Function::SplitPart => Value::Text(split_part(&text(0), &text(1), integer(2)?)?),
+            Function::Lpad | Function::Rpad => {
+                let fill = if has_argument(2) { text(2) } else { " ".to_string() };
+                Value::Text(pad(&text(0), integer(1)?, &fill, *self == Function::Lpad)?)
+            },
+            Function::StartsWith => Value::Integer(text(0).starts_with(&text(1)) as i64),
+            Function::Round => round(number(0)?, if has_argument(1) { integer(1)? } else { 0 })?,
+            Function::Abs => match number(0)? {
+                Value::Integer(value) => Value::Integer(value.checked_abs()
+                    .ok_or_else(|| arithmetic_error(format!("Integer overflow when taking the absolute value of {}", value)))?),
+                value => Value::Float(value.as_float().unwrap_or_default().abs())
+            },
+            Function::Floor | Function::Ceil => match number(0)? {
+                Value::Float(value) if *self == Function::Floor => Value::Float(value.floor()),
+                Value::Float(value) => Value::Float(value.ceil()),
+                value => value.clone()
+            },
+            Function::Power => power(number(0)?, number(1)?)?,
+            Function::Ln => {
+                let value = number(0)?.as_float().unwrap_or_default();
+                if value <= 0.0 {
+                    return Err(arithmetic_error(format!("Cannot take the logarithm of {}", arguments[0])));
+                }
+                Value::Float(value.ln())
+            },
+            Function::Mod => remainder(number(0)?, number(1)?)?,
+            // A truncated date stays a date
+            Function::DateTrunc => {
+                let truncated = date_time::truncate(temporal(1)?, &text(0))?;
+                match &arguments[1] {
+                    Value::Date(_) => Value::Date(truncated.date()),
+                    _ => Value::Timestamp(truncated)
+                }
+            },
+            Function::Extract => Value::Integer(date_time::extract(temporal(1)?, &text(0))?),
+            Function::DateDiff => Value::Integer(date_time::difference(&text(0), temporal(1)?, temporal(2)?)?),
+            Function::Concat => unreachable!("CONCAT is applied before checking for NULL arguments")
+        })
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = FUNCTIONS.iter()
+            .find(|(_, function)| function == self)
+            .map(|(name, _)| *name)
+            .unwrap_or_default();
+        write!(f, "{}", name)
+    }
+}
+
+// Halves are rounded away from zero, a negative number of digits rounds to tens, hundreds and so on
+fn round(value: &Value, digits: i64) -> Result<Value, Error> {
+    match value {
+        Value::Integer(integer) if digits < 0 => {
+            // Rounding to more digits than an integer can have produces 0
+            let factor = match u32::try_from(digits.unsigned_abs()).ok().and_then(|exponent| 10i64.checked_pow(exponent)) {
+                Some(factor) => factor,
+                None => return Ok(Value::Integer(0))
+            };
+            let remainder = integer % factor;
+            let truncated = integer - remainder;
+            if remainder.abs() >= factor - remainder.abs() {
+                truncated.checked_add(remainder.signum() * factor)
+                    .map(Value::Integer)
+                    .ok_or_else(|| arithmetic_error(format!("Integer overflow when rounding {}", integer)))
+            } else {
+                Ok(Value::Integer(truncated))
+            }
+        },
+        Value::Integer(_) => Ok(value.clone()),
+        value => {
+            let value = value.as_float().unwrap_or_default();
+            let factor = 10f64.powi(digits.clamp(-308, 308) as i32);
+            let rounded = (value * factor).round() / factor;
+            // A float has fewer significant digits than can be scaled by a large factor
+            Ok(Value::Float(if rounded.is_finite() { rounded } else { value }))
+        }
+    }
+}
+
+fn power(base: &Value, exponent: &Value) -> Result<Value, Error> {
+    let (x, y) = (base.as_float().unwrap_or_default(), exponent.as_float().unwrap_or_default());
+    if x == 0.0 && y < 0.0 {
+        return Err(arithmetic_error(format!("Division by zero when raising {} to the power of {}", base, exponent)));
+    }
+    match x.powf(y) {
+        result if result.is_nan() =>
+            Err(arithmetic_error(format!("Cannot raise {} to the fractional power of {}", base, exponent))),
+        result if result.is_infinite() && x.is_finite() && y.is_finite() =>
+            Err(arithmetic_error(format!("Overflow when raising {} to the power of {}", base, exponent))),
+        result => Ok(Value::Float(result))
+    }
+}
+
+// The remainder has the sign of the dividend
+fn remainder(dividend: &Value, divisor: &Value) -> Result<Value, Error> {
+    if divisor.as_float() == Some(0.0) {
+        return Err(arithmetic_error(format!("Division by zero when taking the remainder of {} divided by {}", dividend, divisor)));
+    }
+    Ok(match (dividend, divisor) {
+        (Value::Integer(x), Value::Integer(y)) => Value::Integer(x.wrapping_rem(*y)),
+        (x, y) => Value::Float(x.as_float().unwrap_or_default() % y.as_float().unwrap_or_default())
+    })
+}
+
+// Positions start at 1, the positions before the first character still count towards the length
+fn substring(text: &str, start: i64, length: Option<i64>) -> Result<String, Error> {
+    let end = match length {
+        Some(length) if length < 0 => return Err(anyhow!("Length of the substring cannot be negative, found {}", length)),
+        Some(length) => start.saturating_add(length),
+        None => i64::MAX
+    };
+    let characters_count = text.chars().count() as i64;
+    let first = (start.max(1) - 1).min(characters_count);
+    let last = (end.max(1) - 1).clamp(first, characters_count);
+    Ok(text.chars().skip(first as usize).take((last - first) as usize).collect())
+}
+
+// Positions start at 1, negative positions count the fields from the end
+fn split_part(text: &str, delimiter: &str, position: i64) -> Result<String, Error> {
+    if position == 0 {
+        return Err(anyhow!("Position of the field in SPLIT_PART cannot be 0"));
+    }
+    let fields: Vec<&str> = if delimiter.is_empty() {
+        vec![text]
+    } else {
+        text.split(delimiter).collect()
+    };
+    let field_index = if position > 0 {
+        position - 1
+    } else {
+        fields.len() as i64 + position
+    };
+    Ok(usize::try_from(field_index).ok()
+        .and_then(|field_index| fields.get(field_index))
+        .unwrap_or(&"")
+        .to_string())
+}
+
+// The length of a padded text is limited, so that a mistyped length does not exhaust the memory
+const MAX_PADDED_LENGTH: i64 = 10_000_000;
+
+// A text longer than the length is truncated, otherwise the fill is repeated up to the length
+fn pad(text: &str, length: i64, fill: &str, at_start: bool) -> Result<String, Error> {
+    if length > MAX_PADDED_LENGTH {
+        return Err(anyhow!("Length of the padded text cannot exceed {}, found {}", MAX_PADDED_LENGTH, length));
+    }
+    let length = length.max(0) as usize;
+    let characters_count = text.chars().count();
+    if characters_count >= length || fill.is_empty() {
+        return Ok(text.chars().take(length).collect());
+    }
+    let padding: String = fill.chars().cycle().take(length - characters_count).collect();
+    if at_start {
+        Ok(padding + text)
+    } else {
+        Ok(text.to_string() + &padding)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::value::ArithmeticError;
+
+    fn text(value: &str) -> Value {
+        Value::Text(value.to_string())
+    }
+
+    #[test]
+    fn should_apply_string_functions() {
+        let expected_results = vec![
+            (Function::Upper, vec![text("Berlin")], text("BERLIN")),
+            (Function::Lower, vec![text("Berlin")], text("berlin")),
+            (Function::Trim, vec![text("  a b \t")], text("a b")),
+            (Function::Length, vec![text("Zürich")], Value::Integer(6)),
+            (Function::Substr, vec![text("hello"), Value::Integer(2), Value::Integer(3)], text("ell")),
+            (Function::Substr, vec![text("hello"), Value::Integer(0), Value::Integer(2)], text("h")),
+            (Function::Substr, vec![text("hello"), Value::Integer(4)], text("lo")),
+            (Function::Substr, vec![text("hello"), Value::Integer(9)], text("")),
+            (Function::Replace, vec![text("a-b-c"), text("-"), text("+")], text("a+b+c")),
+            (Function::Replace, vec![text("abc"), text(""), text("+")], text("abc")),
+            (Function::Concat, vec![text("a"), Value::Null, Value::Integer(1), Value::Float(2.5)], text("a12.5")),
+            (Function::SplitPart, vec![text("a,b,c"), text(","), Value::Integer(2)], text("b")),
+            (Function::SplitPart, vec![text("a,b,c"), text(","), Value::Integer(-1)], text("c")),
+            (Function::SplitPart, vec![text("a,b,c"), text(","), Value::Integer(4)], text("")),
+            (Function::Lpad, vec![Value::Integer(42), Value::Integer(5), text("0")], text("00042")),
+            (Function::Lpad, vec![text("abc"), Value::Integer(2)], text("ab")),
+            (Function::Rpad, vec![text("ab"), Value::Integer(7), text("xy")], text("abxyxyx")),
+            (Function::Rpad, vec![text("ab"), Value::Integer(4)], text("ab  ")),
+            (Function::StartsWith, vec![text("Berlin"), text("Ber")], Value::Integer(1)),
+            (Function::StartsWith, vec![text("Berlin"), text("ber")], Value::Integer(0)),
+            (Function::Upper, vec![Value::Null], Value::Null),
+            (Function::Substr, vec![text("hello"), Value::Null], Value::Null)
+        ];
+        for (function, arguments, expected_result) in expected_results.into_iter() {
+            assert_eq!(function.apply(&arguments).unwrap(), expected_result, "{}({:?})", function, arguments);
+        }
+        assert_eq!(Function::Substr.apply(&[text("a"), Value::Integer(1), Value::Integer(-1)]).unwrap_err().to_string(),
+            "Length of the substring cannot be negative, found -1");
+        assert_eq!(Function::Lpad.apply(&[text("a"), Value::Integer(i64::MAX)]).unwrap_err().to_string(),
+            "Length of the padded text cannot exceed 10000000, found 9223372036854775807");
+        assert_eq!(Function::Substr.apply(&[text("a"), text("b")]).unwrap_err().to_string(),
+            "Function SUBSTR expects integer as argument 2, found b");
+    }
+
+    #[test]
+    fn should_check_arity_and_types_of_arguments() {
+        assert_eq!(Function::from("SPLIT_PART"), Some(Function::SplitPart));
+        assert_eq!(Function::from("split_part"), None);
+        assert_eq!(Function::Length.check_arity(2), Err("function LENGTH expects 1 argument(s), found 2".to_string()));
+        assert_eq!(Function::Substr.check_arity(1), Err("function SUBSTR expects 2 to 3 argument(s), found 1".to_string()));
+        assert_eq!(Function::Concat.check_arity(0), Err("function CONCAT expects at least 1 argument(s), found 0".to_string()));
+        assert_eq!(Function::Concat.check_arity(5), Ok(()));
+        assert_eq!(Function::Length.check_argument_types(&[Some(ColumnType::Text)]).unwrap(), Some(ColumnType::Integer));
+        assert_eq!(Function::Lpad.check_argument_types(&[None, Some(ColumnType::Integer)]).unwrap(), Some(ColumnType::Text));
+        assert_eq!(Function::Concat.check_argument_types(&[Some(ColumnType::Float), Some(ColumnType::Text)]).unwrap(), Some(ColumnType::Text));
+        assert_eq!(Function::Upper.check_argument_types(&[Some(ColumnType::Integer)]).unwrap_err().to_string(),
+            "Function UPPER expects text as argument 1, found integer");
+        assert_eq!(Function::Lpad.check_argument_types(&[Some(ColumnType::Integer), Some(ColumnType::Integer), Some(ColumnType::Text)]).unwrap_err().to_string(),
+            "Function LPAD expects text as argument 1, found integer");
+        assert_eq!(Function::Substr.check_argument_types(&[Some(ColumnType::Text), Some(ColumnType::Float)]).unwrap_err().to_string(),
+            "Function SUBSTR expects integer as argument 2, found float");
+        assert_eq!(Function::Round.check_argument_types(&[Some(ColumnType::Integer), Some(ColumnType::Integer)]).unwrap(), Some(ColumnType::Integer));
+        assert_eq!(Function::Mod.check_argument_types(&[Some(ColumnType::Integer), Some(ColumnType::Float)]).unwrap(), Some(ColumnType::Float));
+        assert_eq!(Function::Abs.check_argument_types(&[None]).unwrap(), None);
+        assert_eq!(Function::Ln.check_argument_types(&[None]).unwrap(), Some(ColumnType::Float));
+        assert_eq!(Function::Power.check_argument_types(&[Some(ColumnType::Text), Some(ColumnType::Integer)]).unwrap_err().to_string(),
+            "Function POWER expects numeric as argument 1, found text");
+    }
+
+    #[test]
+    fn should_apply_numeric_functions() {
+        let expected_results = vec![
+            (Function::Round, vec![Value::Float(2.5)], Value::Float(3.0)),
+            (Function::Round, vec![Value::Float(-2.5)], Value::Float(-3.0)),
+            (Function::Round, vec![Value::Float(891.846), Value::Integer(2)], Value::Float(891.85)),
+            (Function::Round, vec![Value::Float(1234.5), Value::Integer(-2)], Value::Float(1200.0)),
+            (Function::Round, vec![Value::Float(0.1), Value::Integer(400)], Value::Float(0.1)),
+            (Function::Round, vec![Value::Integer(1250), Value::Integer(-2)], Value::Integer(1300)),
+            (Function::Round, vec![Value::Integer(-1249), Value::Integer(-2)], Value::Integer(-1200)),
+            (Function::Round, vec![Value::Integer(7), Value::Integer(-20)], Value::Integer(0)),
+            (Function::Round, vec![Value::Integer(7), Value::Integer(i64::MIN)], Value::Integer(0)),
+            (Function::Round, vec![Value::Float(7.5), Value::Integer(i64::MIN)], Value::Float(0.0)),
+            (Function::Round, vec![Value::Integer(7), Value::Integer(2)], Value::Integer(7)),
+            (Function::Abs, vec![Value::Integer(-7)], Value::Integer(7)),
+            (Function::Abs, vec![Value::Float(-0.5)], Value::Float(0.5)),
+            (Function::Floor, vec![Value::Float(-1.5)], Value::Float(-2.0)),
+            (Function::Ceil, vec![Value::Float(1.2)], Value::Float(2.0)),
+            (Function::Ceil, vec![Value::Integer(3)], Value::Integer(3)),
+            (Function::Power, vec![Value::Integer(2), Value::Integer(10)], Value::Float(1024.0)),
+            (Function::Power, vec![Value::Integer(4), Value::Float(0.5)], Value::Float(2.0)),
+            (Function::Ln, vec![Value::Integer(1)], Value::Float(0.0)),
+            (Function::Mod, vec![Value::Integer(-7), Value::Integer(3)], Value::Integer(-1)),
+            (Function::Mod, vec![Value::Float(7.5), Value::Integer(2)], Value::Float(1.5)),
+            (Function::Mod, vec![Value::Integer(i64::MIN), Value::Integer(-1)], Value::Integer(0)),
+            (Function::Abs, vec![Value::Null], Value::Null)
+        ];
+        for (function, arguments, expected_result) in expected_results.into_iter() {
+            assert_eq!(function.apply(&arguments).unwrap(), expected_result, "{}({:?})", function, arguments);
+        }
+        let expected_errors = vec![
+            (Function::Abs, vec![Value::Integer(i64::MIN)], "Integer overflow when taking the absolute value of -9223372036854775808"),
+            (Function::Round, vec![Value::Integer(i64::MAX), Value::Integer(-1)], "Integer overflow when rounding 9223372036854775807"),
+            (Function::Power, vec![Value::Integer(0), Value::Integer(-1)], "Division by zero when raising 0 to the power of -1"),
+            (Function::Power, vec![Value::Integer(-8), Value::Float(0.5)], "Cannot raise -8 to the fractional power of 0.5"),
+            (Function::Power, vec![Value::Integer(10), Value::Integer(400)], "Overflow when raising 10 to the power of 400"),
+            (Function::Ln, vec![Value::Integer(0)], "Cannot take the logarithm of 0"),
+            (Function::Mod, vec![Value::Integer(7), Value::Float(0.0)], "Division by zero when taking the remainder of 7 divided by 0")
+        ];
+        for (function, arguments, expected_error) in expected_errors.into_iter() {
+            let error = function.apply(&arguments).unwrap_err();
+            assert_eq!(error.to_string(), expected_error);
+            assert!(error.is::<ArithmeticError>(), "{}", expected_error);
+        }
+        assert_eq!(Function::Abs.apply(&[Value::Text("a".to_string())]).unwrap_err().to_string(),
+            "Function ABS expects numeric as argument 1, found a");
+    }
+}
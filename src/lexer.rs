@@ -0,0 +1,353 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::str::CharIndices;
+use crate::parse_error::{ParseError, Span};
+
+// Keywords are not distinguished from other identifiers by the lexer, whether an identifier
+// is a keyword depends on where it is found in the query. A quoted identifier is never a keyword.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Token {
+    Identifier(String),
+    QuotedIdentifier(String),
+    Text(String),
+    Number(String),
+    Operator(String),
+    Comma,
+    LeftParenthesis,
+    RightParenthesis
+}
+
+impl Token {
+    pub(crate) fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Identifier(name) if name == keyword)
+    }
+
+    pub(crate) fn is_operator(&self, operator: &str) -> bool {
+        matches!(self, Token::Operator(token_operator) if token_operator == operator)
+    }
+
+    // Whether the token can end an operand, so that a following minus is an operator
+    // rather than the sign of a number
+    fn ends_operand(&self) -> bool {
+        matches!(self, Token::Identifier(_) | Token::QuotedIdentifier(_) | Token::Text(_) | Token::Number(_) | Token::RightParenthesis)
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Identifier(name) => write!(f, "{}", name),
+            Token::QuotedIdentifier(name) => write!(f, "\"{}\"", name.replace('"', "\"\"")),
+            Token::Text(text) => write!(f, "'{}'", text.replace('\\', "\\\\").replace('\'', "\\'")),
+            Token::Number(number) => write!(f, "{}", number),
+            Token::Operator(operator) => write!(f, "{}", operator),
+            Token::Comma => write!(f, ","),
+            Token::LeftParenthesis => write!(f, "("),
+            Token::RightParenthesis => write!(f, ")")
+        }
+    }
+}
+
+// The tokens together with their spans in the query, so that errors can point at the place in it
+pub(crate) struct Tokens<'a> {
+    input: &'a str,
+    tokens: Vec<Token>,
+    spans: Vec<Span>
+}
+
+impl Tokens<'_> {
+    pub(crate) fn get(&self, position: usize) -> Option<&Token> {
+        self.tokens.get(position)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    // Past the last token this is the end of the query, ignoring the trailing whitespace
+    pub(crate) fn span(&self, position: usize) -> Span {
+        self.spans.get(position).cloned().unwrap_or_else(|| {
+            let end_of_query = self.input.trim_end().len();
+            Span { start: end_of_query, end: end_of_query }
+        })
+    }
+
+    // The span covering the tokens from the start position up to (not including) the end position
+    pub(crate) fn span_between(&self, start_position: usize, end_position: usize) -> Span {
+        Span {
+            start: self.span(start_position).start,
+            end: self.span(end_position.max(start_position + 1) - 1).end
+        }
+    }
+
+    pub(crate) fn error(&self, message: &str, span: Span) -> ParseError {
+        ParseError::new(message, span, self.input)
+    }
+
+    pub(crate) fn expected(&self, expected: &str, position: usize) -> ParseError {
+        let found = self.get(position).map(|token| token.to_string()).unwrap_or("end of query".to_string());
+        self.error(&format!("expected {}, found {}", expected, found), self.span(position))
+    }
+}
+
+const OPERATORS: [&str; 14] = ["<=", ">=", "<>", "!=", "!~", "||", "=", "<", ">", "~", "*", "-", "+", "/"];
+
+pub(crate) fn tokenize(input: &str) -> Result<Tokens<'_>, ParseError> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut spans: Vec<Span> = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some(&(position, char)) = chars.peek() {
+        let is_number_sign = char == '-'
+            && input[position + 1..].starts_with(|next_char: char| next_char.is_ascii_digit())
+            && !tokens.last().is_some_and(|token| token.ends_operand());
+        if char.is_whitespace() {
+            chars.next();
+            continue;
+        } else if char.is_ascii_digit() || is_number_sign {
+            tokens.push(Token::Number(read_number(input, &mut chars)));
+        } else if char.is_alphabetic() || char == '_' {
+            let end = read_while(input, &mut chars, |char| char.is_alphanumeric() || char == '_' || char == '.');
+            tokens.push(Token::Identifier(input[position..end].to_string()));
+        } else if char == '"' || char == '`' {
+            tokens.push(Token::QuotedIdentifier(read_quoted_identifier(input, &mut chars)?));
+        } else if char == '\'' {
+            tokens.push(Token::Text(read_text(input, &mut chars)?));
+        } else if char == ',' || char == '(' || char == ')' {
+            chars.next();
+            tokens.push(match char {
+                ',' => Token::Comma,
+                '(' => Token::LeftParenthesis,
+                _ => Token::RightParenthesis
+            });
+        } else {
+            let operator = OPERATORS.iter()
+                .find(|operator| input[position..].starts_with(*operator))
+                .ok_or_else(|| ParseError::new(
+                    &format!("unexpected character {}", char),
+                    Span { start: position, end: position + char.len_utf8() },
+                    input))?;
+            for _ in 0..operator.len() {
+                chars.next();
+            }
+            tokens.push(Token::Operator(operator.to_string()));
+        }
+        let end = chars.peek().map(|(position, _)| *position).unwrap_or(input.len());
+        spans.push(Span { start: position, end });
+    }
+    Ok(Tokens {
+        input,
+        tokens,
+        spans
+    })
+}
+
+// Returns the position right after the last consumed character
+fn read_while(input: &str, chars: &mut Peekable<CharIndices>, predicate: impl Fn(char) -> bool) -> usize {
+    let mut end = chars.peek().map(|(position, _)| *position).unwrap_or(input.len());
+    while let Some(&(position, char)) = chars.peek() {
+        if !predicate(char) {
+            break;
+        }
+        end = position + char.len_utf8();
+        chars.next();
+    }
+    end
+}
+
+// An optional sign, the integer part, an optional fractional part and an optional exponent
+fn read_number(input: &str, chars: &mut Peekable<CharIndices>) -> String {
+    let (start, _) = chars.next().unwrap_or_default();
+    let mut end = read_while(input, chars, |char| char.is_ascii_digit());
+    let is_followed_by = |end: usize, prefix: &str| input[end..].starts_with(prefix)
+        && input[end + prefix.len()..].starts_with(|char: char| char.is_ascii_digit());
+    if is_followed_by(end, ".") {
+        chars.next();
+        end = read_while(input, chars, |char| char.is_ascii_digit());
+    }
+    if let Some(exponent_prefix) = ["e", "E", "e-", "E-", "e+", "E+"].iter().find(|prefix| is_followed_by(end, prefix)) {
+        for _ in 0..exponent_prefix.len() {
+            chars.next();
+        }
+        end = read_while(input, chars, |char| char.is_ascii_digit());
+    }
+    input[start..end].to_string()
+}
+
+// The identifier is enclosed in double quotes or backticks, the enclosing quote is escaped by doubling it
+fn read_quoted_identifier(input: &str, chars: &mut Peekable<CharIndices>) -> Result<String, ParseError> {
+    let (start, quote) = chars.next().unwrap_or_default();
+    let mut name = String::new();
+    while let Some((_, char)) = chars.next() {
+        if char != quote {
+            name.push(char);
+        } else if chars.peek().is_some_and(|(_, next_char)| *next_char == quote) {
+            chars.next();
+            name.push(quote);
+        } else if name.is_empty() {
+            return Err(ParseError::new("quoted identifier is empty", Span { start, end: start + 2 }, input));
+        } else {
+            return Ok(name);
+        }
+    }
+    Err(ParseError::new("quoted identifier is not terminated", Span { start, end: input.len() }, input))
+}
+
+// A string is enclosed in single quotes, a backslash escapes the quote, the backslash itself and
+// stands for a newline or a tab in \n and \t
+fn read_text(input: &str, chars: &mut Peekable<CharIndices>) -> Result<String, ParseError> {
+    let (start, _) = chars.next().unwrap_or_default();
+    let mut text = String::new();
+    loop {
+        match chars.next() {
+            Some((_, '\'')) => return Ok(text),
+            Some((position, '\\')) => match chars.next() {
+                Some((_, '\'')) => text.push('\''),
+                Some((_, '\\')) => text.push('\\'),
+                Some((_, 'n')) => text.push('\n'),
+                Some((_, 't')) => text.push('\t'),
+                Some((_, char)) => return Err(ParseError::new(
+                    &format!("unknown escape sequence \\{}", char),
+                    Span { start: position, end: position + 1 + char.len_utf8() },
+                    input)),
+                None => break
+            },
+            Some((_, char)) => text.push(char),
+            None => break
+        }
+    }
+    Err(ParseError::new("string is not terminated", Span { start, end: input.len() }, input))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn identifier(name: &str) -> Token {
+        Token::Identifier(name.to_string())
+    }
+
+    fn operator(operator: &str) -> Token {
+        Token::Operator(operator.to_string())
+    }
+
+    #[test]
+    fn should_tokenize_query() {
+        let tokens = tokenize("PROJECT col1 ,col2,COUNT(*) FILTER col_3>=-1.5e3 AND cities.name!='New York'").unwrap();
+        assert_eq!(tokens.tokens, vec![
+            identifier("PROJECT"),
+            identifier("col1"),
+            Token::Comma,
+            identifier("col2"),
+            Token::Comma,
+            identifier("COUNT"),
+            Token::LeftParenthesis,
+            operator("*"),
+            Token::RightParenthesis,
+            identifier("FILTER"),
+            identifier("col_3"),
+            operator(">="),
+            Token::Number("-1.5e3".to_string()),
+            identifier("AND"),
+            identifier("cities.name"),
+            operator("!="),
+            Token::Text("New York".to_string())
+        ]);
+    }
+
+    #[test]
+    fn should_unescape_strings() {
+        let tokens = tokenize(r#"'say \'hi\'' 'a\\b' 'tab\there'"#).unwrap();
+        assert_eq!(tokens.tokens, vec![
+            Token::Text("say 'hi'".to_string()),
+            Token::Text("a\\b".to_string()),
+            Token::Text("tab\there".to_string())
+        ]);
+        assert_eq!(tokens.tokens.iter().map(|token| token.to_string()).collect::<Vec<String>>(), vec![r#"'say \'hi\''"#, r#"'a\\b'"#, "'tab\there'"]);
+    }
+
+    #[test]
+    fn should_tokenize_quoted_identifiers() {
+        let tokens = tokenize(r#"PROJECT "Area (km2)", `FILTER`, "say ""hi""", `a``b` FILTER "x" = 'x'"#).unwrap();
+        assert_eq!(tokens.tokens, vec![
+            identifier("PROJECT"),
+            Token::QuotedIdentifier("Area (km2)".to_string()),
+            Token::Comma,
+            Token::QuotedIdentifier("FILTER".to_string()),
+            Token::Comma,
+            Token::QuotedIdentifier("say \"hi\"".to_string()),
+            Token::Comma,
+            Token::QuotedIdentifier("a`b".to_string()),
+            identifier("FILTER"),
+            Token::QuotedIdentifier("x".to_string()),
+            operator("="),
+            Token::Text("x".to_string())
+        ]);
+        assert!(!tokens.tokens[3].is_keyword("FILTER"));
+        match tokenize("PROJECT \"\" FILTER") {
+            Err(e) => assert_eq!(e.to_string(), "quoted identifier is empty\nPROJECT \"\" FILTER\n        ^^"),
+            Ok(_) => panic!("Error expected")
+        }
+    }
+
+    #[test]
+    fn should_treat_minus_after_operand_as_operator() {
+        let tokens = tokenize("LIMIT -5 ) -2").unwrap();
+        assert_eq!(tokens.tokens, vec![identifier("LIMIT"), operator("-"), Token::Number("5".to_string()), Token::RightParenthesis, operator("-"), Token::Number("2".to_string())]);
+    }
+
+    #[test]
+    fn should_tokenize_arithmetic_and_concatenation_operators() {
+        let tokens = tokenize("a/b+-1||'x'*2").unwrap();
+        assert_eq!(tokens.tokens, vec![
+            identifier("a"),
+            operator("/"),
+            identifier("b"),
+            operator("+"),
+            Token::Number("-1".to_string()),
+            operator("||"),
+            Token::Text("x".to_string()),
+            operator("*"),
+            Token::Number("2".to_string())
+        ]);
+    }
+
+    #[test]
+    fn should_tokenize_regex_match_operators() {
+        let tokens = tokenize("a~'^x' !~'y'").unwrap();
+        assert_eq!(tokens.tokens, vec![
+            identifier("a"),
+            operator("~"),
+            Token::Text("^x".to_string()),
+            operator("!~"),
+            Token::Text("y".to_string())
+        ]);
+    }
+
+    #[test]
+    fn should_track_spans_of_tokens() {
+        let tokens = tokenize("PROJECT  é, 'a b'<=1.5 ").unwrap();
+        assert_eq!(tokens.spans, vec![
+            Span { start: 0, end: 7 },
+            Span { start: 9, end: 11 },
+            Span { start: 11, end: 12 },
+            Span { start: 13, end: 18 },
+            Span { start: 18, end: 20 },
+            Span { start: 20, end: 23 }
+        ]);
+        assert_eq!(tokens.span(6), Span { start: 23, end: 23 });
+        assert_eq!(tokens.span_between(1, 4), Span { start: 9, end: 18 });
+        assert_eq!(tokens.expected("column", 6).message, "expected column, found end of query");
+    }
+
+    #[test]
+    fn should_produce_error_for_unterminated_string_and_unknown_characters() {
+        match tokenize("PROJECT a FILTER b = 'abc") {
+            Err(e) => assert_eq!(e.to_string(), "string is not terminated\nPROJECT a FILTER b = 'abc\n                     ^^^^"),
+            Ok(_) => panic!("Error expected")
+        }
+        match tokenize("PROJECT a; b") {
+            Err(e) => assert_eq!(e.to_string(), "unexpected character ;\nPROJECT a; b\n         ^"),
+            Ok(_) => panic!("Error expected")
+        }
+    }
+}
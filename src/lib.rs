@@ -1,13 +1,23 @@
 pub mod value;
 pub use value::Value;
+pub mod date_time;
 
 pub mod table;
 pub use table::Table;
 pub use table::IndexedTable;
+pub use table::LoadOptions;
 
+pub mod database;
+pub use database::Database;
+pub use database::IndexedDatabase;
+
+mod lexer;
+pub mod parse_error;
+pub use parse_error::ParseError;
+pub mod function;
 pub mod query;
-pub use query::Query;
+pub use query::{Dialect, Query};
 
 pub mod query_engine;
-pub use query_engine::execute;
+pub use query_engine::{execute, execute_on_database, execute_on_database_with_options, ArithmeticErrors, ExecutionOptions};
 pub use query_engine::{ResultSet, ResultSetRow};
\ No newline at end of file
@@ -1,8 +1,9 @@
 use anyhow::{anyhow, Result, Error};
 use std::fs::File;
+use std::path::Path;
 use std::{env, process};
 use std::io::{self, Write};
-use simple_query_engine::{Query, Table};
+use simple_query_engine::{ArithmeticErrors, Database, Dialect, ExecutionOptions, LoadOptions, Query, Table};
 
 fn main() {
     match run() {
@@ -16,18 +17,50 @@ fn main() {
 
 const EXIT_COMMANDS: [&str; 3] = ["exit", "quit", "q"];
 
+const NULL_VALUES_OPTION: &str = "--null-values";
+
+const DIALECT_OPTION: &str = "--dialect";
+
+const ARITHMETIC_ERRORS_OPTION: &str = "--arithmetic-errors";
+
 fn run() -> Result<(), Error> {
     let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        eprintln!("Error: missing the CSV file path argument.");
-        eprintln!("Usage: {} <path_to_csv>", args[0]);
-        Err(anyhow!("CSV file path not provided"))
-    } else {
-        let file_path = &args[1];
-        let file = File::open(file_path)?;
-        let mut reader = csv::Reader::from_reader(file);
-        let table = Table::load_from(&mut reader)?;
-        let indexed_table = table.build_indices()?;
+    let mut table_paths: Vec<&String> = Vec::new();
+    let mut load_options = LoadOptions::default();
+    let mut dialect = Dialect::Auto;
+    let mut execution_options = ExecutionOptions::default();
+    let mut arg_position = 1;
+    while arg_position < args.len() {
+        if args[arg_position] == NULL_VALUES_OPTION {
+            let null_values = args.get(arg_position + 1)
+                .ok_or_else(|| anyhow!("Missing the comma separated list of values after {}", NULL_VALUES_OPTION))?;
+            load_options.null_values.extend(null_values.split(',').map(|null_value| null_value.to_string()));
+            arg_position += 2;
+        } else if args[arg_position] == DIALECT_OPTION {
+            dialect = args.get(arg_position + 1)
+                .and_then(|dialect| Dialect::from(dialect))
+                .ok_or_else(|| anyhow!("Missing the query dialect after {}, one of auto, native, sql", DIALECT_OPTION))?;
+            arg_position += 2;
+        } else if args[arg_position] == ARITHMETIC_ERRORS_OPTION {
+            execution_options.arithmetic_errors = args.get(arg_position + 1)
+                .and_then(|arithmetic_errors| ArithmeticErrors::from(arithmetic_errors))
+                .ok_or_else(|| anyhow!("Missing the handling of arithmetic errors after {}, one of fail, null", ARITHMETIC_ERRORS_OPTION))?;
+            arg_position += 2;
+        } else {
+            table_paths.push(&args[arg_position]);
+            arg_position += 1;
+        }
+    }
+    if !table_paths.is_empty() {
+        let mut database = Database::new();
+        for table_path in table_paths.into_iter() {
+            let (table_name, file_path) = parse_table_path(table_path)?;
+            let file = File::open(file_path)?;
+            let mut reader = csv::Reader::from_reader(file);
+            let table = Table::load_with_options_from(&mut reader, &load_options)?;
+            database.add_table(&table_name, table)?;
+        }
+        let indexed_database = database.build_indices()?;
         let mut should_exit = false;
         while !should_exit {
             let stdin = io::stdin();
@@ -39,11 +72,11 @@ fn run() -> Result<(), Error> {
             if EXIT_COMMANDS.contains(&input.trim()) {
                 should_exit = true;
             } else {
-                match Query::parse(&input) {
+                match Query::parse_with_dialect(&input, dialect) {
                     Ok(query) =>
-                        match simple_query_engine::execute(&query, &indexed_table) {
+                        match simple_query_engine::execute_on_database_with_options(&query, &indexed_database, &execution_options) {
                             Ok(result_set) => {
-                                let header = query.column_names.join(",");
+                                let header = result_set.columns.join(",");
                                 let header_separator = "-".repeat(header.len());
                                 print!("{}\n{}\n", header, header_separator);
                                 for row in result_set.rows.iter() {
@@ -59,5 +92,25 @@ fn run() -> Result<(), Error> {
             }
         }
         Ok(())
+    } else {
+        eprintln!("Error: missing the CSV file path argument.");
+        eprintln!("Usage: {} [{} <comma_separated_values>] [{} auto|native|sql] [{} fail|null] [<table_name>=]<path_to_csv>...",
+            args[0], NULL_VALUES_OPTION, DIALECT_OPTION, ARITHMETIC_ERRORS_OPTION);
+        Err(anyhow!("CSV file path not provided"))
+    }
+}
+
+// The table name is either given explicitly as "name=path.csv" or is the file name without the extension,
+// a name made of letters, digits and underscores only, so that paths like "./year=2024/data.csv" are not split
+fn parse_table_path(table_path: &str) -> Result<(String, &str), Error> {
+    let is_table_name = |name: &str| !name.is_empty() && name.chars().all(|char| char.is_alphanumeric() || char == '_');
+    match table_path.split_once('=').filter(|(table_name, _)| is_table_name(table_name)) {
+        Some((table_name, file_path)) => Ok((table_name.to_string(), file_path)),
+        None => {
+            let table_name = Path::new(table_path).file_stem()
+                .and_then(|file_stem| file_stem.to_str())
+                .ok_or_else(|| anyhow!("Cannot derive table name from the file path {}", table_path))?;
+            Ok((table_name.to_string(), table_path))
+        }
     }
 }
@@ -0,0 +1,56 @@
+use std::fmt;
+
+// Byte offsets of the beginning and of the end of a part of the parsed query
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Span,
+    pub input: String
+}
+
+impl ParseError {
+    pub fn new(message: &str, span: Span, input: &str) -> ParseError {
+        ParseError {
+            message: message.to_string(),
+            span,
+            input: input.to_string()
+        }
+    }
+}
+
+// The message is followed by the line of the query containing the error and a caret
+// under the offending part of it
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let line_start = self.input[..self.span.start].rfind('\n').map(|position| position + 1).unwrap_or(0);
+        let line_end = self.input[self.span.start..].find('\n').map(|position| self.span.start + position).unwrap_or(self.input.len());
+        let caret_offset = self.input[line_start..self.span.start].chars().count();
+        let caret_length = self.input[self.span.start..self.span.end.min(line_end)].chars().count().max(1);
+        write!(f, "{}\n{}\n{}{}", self.message, &self.input[line_start..line_end], " ".repeat(caret_offset), "^".repeat(caret_length))
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_render_caret_under_offending_part_of_query() {
+        let error = ParseError::new("expected keyword FILTER, found FILTRE", Span { start: 19, end: 25 }, "PROJECT col1, col2 FILTRE col3 > 1");
+        assert_eq!(error.to_string(), "expected keyword FILTER, found FILTRE\nPROJECT col1, col2 FILTRE col3 > 1\n                   ^^^^^^");
+    }
+
+    #[test]
+    fn should_render_caret_on_the_line_containing_error() {
+        let error = ParseError::new("expected column to project, found end of query", Span { start: 16, end: 16 }, "PROJECT a,\n  b, ");
+        assert_eq!(error.to_string(), "expected column to project, found end of query\n  b, \n     ^");
+    }
+}
@@ -1,267 +1,2547 @@
-use anyhow::{anyhow, Context, Error, Result};
+use std::fmt;
+use anyhow::{anyhow, Error, Result};
+use regex::Regex;
+use crate::date_time::{self, Interval};
+use crate::function::Function;
+use crate::lexer::{self, Token, Tokens};
+use crate::parse_error::ParseError;
 use crate::value::Value;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Query {
-    pub column_names: Vec<String>,
-    pub filter: Option<Filter>
+    pub distinct: bool,
+    pub projection: Vec<ProjectionItem>,
+    pub from: Option<String>,
+    pub joins: Vec<Join>,
+    pub filter: Option<Predicate>,
+    pub group_by: Vec<Expression>,
+    pub having: Option<Predicate>,
+    pub order_by: Vec<OrderBy>,
+    pub limit: Option<Limit>
+}
+
+// The keywords of both dialects are reserved in either of them
+const CLAUSE_KEYWORDS: [&str; 10] = ["FROM", "JOIN", "INNER", "LEFT", "FILTER", "WHERE", "GROUP", "HAVING", "ORDER", "LIMIT"];
+
+// The keywords preceding a string to give it a type, such as DATE '2024-01-31'
+const TYPED_LITERAL_KEYWORDS: [&str; 3] = ["DATE", "TIMESTAMP", "INTERVAL"];
+
+// Both dialects share the same grammar and only differ in the keywords starting the projection
+// and the filter: "PROJECT ... FILTER ..." and the SQL "SELECT ... WHERE ..."
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Dialect {
+    Auto,
+    Native,
+    Sql
+}
+
+impl Dialect {
+    pub fn from(input: &str) -> Option<Dialect> {
+        match input {
+            "auto" => Some(Dialect::Auto),
+            "native" => Some(Dialect::Native),
+            "sql" => Some(Dialect::Sql),
+            _ => None
+        }
+    }
+
+    fn projection_keyword(&self) -> &'static str {
+        match self {
+            Dialect::Sql => "SELECT",
+            _ => "PROJECT"
+        }
+    }
+
+    fn filter_keyword(&self) -> &'static str {
+        match self {
+            Dialect::Sql => "WHERE",
+            _ => "FILTER"
+        }
+    }
 }
 
 impl Query {
-    pub fn parse(input: &str) -> Result<Query, Error> {
-        let tokens: Vec<&str> = input.split_whitespace().collect();
-        let (query, final_position) = Query::parse_query(&tokens, 0)?;
+    pub fn parse(input: &str) -> Result<Query, ParseError> {
+        Query::parse_with_dialect(input, Dialect::Auto)
+    }
+
+    // With the automatic detection the dialect is recognized by the first keyword of the query
+    pub fn parse_with_dialect(input: &str, dialect: Dialect) -> Result<Query, ParseError> {
+        let tokens = lexer::tokenize(input)?;
+        let dialect = match dialect {
+            Dialect::Auto if Query::is_keyword_at(&tokens, 0, Dialect::Sql.projection_keyword()) => Dialect::Sql,
+            Dialect::Auto if Query::is_keyword_at(&tokens, 0, Dialect::Native.projection_keyword()) => Dialect::Native,
+            Dialect::Auto => return Err(tokens.expected("keyword PROJECT or SELECT", 0)),
+            dialect => dialect
+        };
+        let (query, final_position) = Query::parse_query(&tokens, 0, dialect)?;
         if final_position == tokens.len() {
             Ok(query)
         } else {
-            Err(anyhow!(format!("Unexpected suffix found in {:?} at position {}", tokens, final_position)))
-        }
-    }
-
-    fn parse_query(tokens: &Vec<&str>, position: usize) -> Result<(Query, usize), Error> {
-        let (column_names, position_after_projection) = Query::parse_projection(tokens, position)?;
-        let (filter, position_after_filter) = Query::parse_filter(tokens, position_after_projection)?;
-        Ok((Query {
-            column_names,
-            filter
-        }, position_after_filter))
-    }
-
-    fn parse_projection(tokens: &Vec<&str>, position: usize) -> Result<(Vec<String>, usize), Error> {
-        if let Some(&token) = tokens.get(position) {
-            if token == "PROJECT" {
-                let mut current_position = position + 1;
-                let mut column_names: Vec<String> = Vec::new();
-                let mut all_columns_read = false;
-                while current_position < tokens.len() && !all_columns_read {
-                    let current_token = tokens[current_position];
-                    if current_token.ends_with(",") {
-                        column_names.push(current_token[..(current_token.len() - 1)].to_string());
-                        current_position = current_position + 1;
-                    } else if current_token != "FILTER" {
-                        column_names.push(current_token.to_string());
-                        all_columns_read = true;
-                        current_position = current_position + 1;
-                    } else {
-                        all_columns_read = true;
-                    }
+            Err(tokens.expected("end of query", final_position))
+        }
+    }
+
+    pub fn is_aggregation(&self) -> bool {
+        !self.group_by.is_empty() || self.having.is_some() || self.projection.iter().any(|item| matches!(item.unaliased(), ProjectionItem::Aggregate(_)))
+    }
+
+    fn parse_query(tokens: &Tokens, position: usize, dialect: Dialect) -> Result<(Query, usize), ParseError> {
+        let (distinct, projection, position_after_projection) = Query::parse_projection(tokens, position, dialect)?;
+        let (from, position_after_from) = Query::parse_from(tokens, position_after_projection)?;
+        let (joins, position_after_joins) = Query::parse_joins(tokens, position_after_from)?;
+        if from.is_none() && !joins.is_empty() {
+            return Err(tokens.error("table to join with has to be specified with FROM", tokens.span(position_after_from)));
+        }
+        let (filter, position_after_filter) = Query::parse_filter(tokens, position_after_joins, dialect)?;
+        let (group_by, position_after_group_by) = Query::parse_group_by(tokens, position_after_filter)?;
+        let (having, position_after_having) = Query::parse_having(tokens, position_after_group_by)?;
+        let (order_by, position_after_order_by) = Query::parse_order_by(tokens, position_after_having)?;
+        let (limit, position_after_limit) = Query::parse_limit(tokens, position_after_order_by)?;
+        let query = Query {
+            distinct,
+            projection,
+            from,
+            joins,
+            filter,
+            group_by,
+            having,
+            order_by,
+            limit
+        };
+        if let Some(having) = &query.having {
+            query.resolve_having(having).map_err(|message| tokens.error(&message, tokens.span_between(position_after_group_by + 1, position_after_having)))?;
+        }
+        Ok((query, position_after_limit))
+    }
+
+    // Returns whether only the distinct rows are projected, as given by the keyword DISTINCT before the projected items
+    fn parse_projection(tokens: &Tokens, position: usize, dialect: Dialect) -> Result<(bool, Vec<ProjectionItem>, usize), ParseError> {
+        if Query::is_keyword_at(tokens, position, dialect.projection_keyword()) {
+            let distinct = Query::is_keyword_at(tokens, position + 1, "DISTINCT");
+            let first_item_position = if distinct { position + 2 } else { position + 1 };
+            let mut current_position = first_item_position;
+            let mut projection: Vec<ProjectionItem> = Vec::new();
+            let mut all_items_read = false;
+            while !all_items_read {
+                match tokens.get(current_position) {
+                    Some(current_token) if !Query::is_clause_keyword(current_token) => {
+                        let (item, position_after_item) = Query::parse_projection_item(tokens, current_position)?;
+                        projection.push(item);
+                        let (position_after_comma, is_followed_by_comma) = Query::skip_comma(tokens, position_after_item);
+                        current_position = position_after_comma;
+                        all_items_read = !is_followed_by_comma;
+                    },
+                    _ => all_items_read = true
                 }
-                if column_names.is_empty() {
-                    Err(anyhow!("Projection column list is empty"))
+            }
+            if projection.is_empty() {
+                Err(tokens.expected("column to project", first_item_position))
+            } else {
+                Ok((distinct, projection, current_position))
+            }
+        } else {
+            Err(tokens.expected(&format!("keyword {}", dialect.projection_keyword()), position))
+        }
+    }
+
+    fn parse_projection_item(tokens: &Tokens, position: usize) -> Result<(ProjectionItem, usize), ParseError> {
+        if tokens.get(position).is_some_and(|token| token.is_operator("*")) {
+            return Query::parse_all_columns(tokens, position);
+        }
+        if let Some((pattern, position_after_pattern)) = Query::parse_matching_columns(tokens, position)? {
+            return Ok((ProjectionItem::MatchingColumns(pattern), position_after_pattern));
+        }
+        let (item, position_after_item) = match Query::parse_aggregate(tokens, position)? {
+            Some((aggregate, position_after_aggregate)) =>
+                (ProjectionItem::Aggregate(aggregate), position_after_aggregate),
+            None => match Query::parse_expression(tokens, position, "column to project")? {
+                (Expression::Column(column_name), position_after_column) =>
+                    (ProjectionItem::Column(column_name), position_after_column),
+                (expression, position_after_expression) =>
+                    (ProjectionItem::Expression(expression), position_after_expression)
+            }
+        };
+        if Query::is_keyword_at(tokens, position_after_item, "AS") {
+            let alias = Query::parse_name(tokens, position_after_item + 1, "alias")?;
+            Ok((ProjectionItem::Aliased { item: Box::new(item), alias }, position_after_item + 2))
+        } else {
+            Ok((item, position_after_item))
+        }
+    }
+
+    fn parse_expression(tokens: &Tokens, position: usize, description: &str) -> Result<(Expression, usize), ParseError> {
+        Query::parse_binary_expression(tokens, position, description, 0)
+    }
+
+    // Precedence climbing, the operands of an operator are the expressions made of operators binding tighter than it
+    fn parse_binary_expression(tokens: &Tokens, position: usize, description: &str, min_precedence: u8) -> Result<(Expression, usize), ParseError> {
+        let (mut expression, mut current_position) = Query::parse_unary_expression(tokens, position, description)?;
+        while let Some(operator) = tokens.get(current_position)
+            .and_then(BinaryOperator::from)
+            .filter(|operator| operator.precedence() >= min_precedence) {
+            let (right, position_after_right) = Query::parse_binary_expression(tokens, current_position + 1, "operand", operator.precedence() + 1)?;
+            expression = Expression::Binary {
+                operator,
+                left: Box::new(expression),
+                right: Box::new(right)
+            };
+            current_position = position_after_right;
+        }
+        Ok((expression, current_position))
+    }
+
+    fn parse_unary_expression(tokens: &Tokens, position: usize, description: &str) -> Result<(Expression, usize), ParseError> {
+        match tokens.get(position) {
+            Some(token) if token.is_operator("-") => {
+                let (negated, position_after_negated) = Query::parse_unary_expression(tokens, position + 1, "operand")?;
+                Ok((Expression::Negation(Box::new(negated)), position_after_negated))
+            },
+            Some(Token::LeftParenthesis) => {
+                let (expression, position_after_expression) = Query::parse_expression(tokens, position + 1, "operand")?;
+                if tokens.get(position_after_expression) == Some(&Token::RightParenthesis) {
+                    Ok((expression, position_after_expression + 1))
                 } else {
-                    Ok((column_names, current_position))
+                    Err(tokens.expected("closing parenthesis", position_after_expression))
+                }
+            },
+            Some(Token::Text(_)) | Some(Token::Number(_)) =>
+                Query::parse_value(tokens, position, description).map(|(value, next_position)| (Expression::Literal(value), next_position)),
+            Some(token) if TYPED_LITERAL_KEYWORDS.iter().any(|keyword| token.is_keyword(keyword)) && matches!(tokens.get(position + 1), Some(Token::Text(_))) =>
+                Query::parse_value(tokens, position, description).map(|(value, next_position)| (Expression::Literal(value), next_position)),
+            Some(token) if token.is_keyword("NULL") =>
+                Ok((Expression::Literal(Value::Null), position + 1)),
+            Some(token) if token.is_keyword("CASE") =>
+                Query::parse_case(tokens, position),
+            Some(Token::Identifier(name)) if tokens.get(position + 1) == Some(&Token::LeftParenthesis) && Function::from(name).is_some() =>
+                Query::parse_function_call(tokens, position),
+            _ => match Query::parse_aggregate(tokens, position)? {
+                // An aggregate is resolved against the aggregates computed for the aggregated rows
+                Some((aggregate, position_after_aggregate)) =>
+                    Ok((Expression::Aggregate(aggregate), position_after_aggregate)),
+                None => {
+                    let column_name = Query::parse_name(tokens, position, description)?;
+                    Ok((Expression::Column(column_name), position + 1))
                 }
+            }
+        }
+    }
+
+    // A string, a possibly negative number or a string preceded by its type: DATE '2024-01-31', TIMESTAMP '2024-01-31 10:00:00'
+    // or INTERVAL '3 days', a timestamp can also be given only by its date
+    fn parse_value(tokens: &Tokens, position: usize, description: &str) -> Result<(Value, usize), ParseError> {
+        match tokens.get(position) {
+            Some(Token::Text(text)) => Ok((Value::Text(text.to_string()), position + 1)),
+            Some(Token::Number(number)) => Value::parse_value(number.to_string())
+                .map(|value| (value, position + 1))
+                .map_err(|error| tokens.error(&error.to_string(), tokens.span(position))),
+            // After a keyword such as BETWEEN or AND the lexer takes the minus for an operator
+            Some(token) if token.is_operator("-") && matches!(tokens.get(position + 1), Some(Token::Number(number)) if !number.starts_with('-')) => {
+                let number = tokens.get(position + 1).map(|token| token.to_string()).unwrap_or_default();
+                Value::parse_value(format!("-{}", number))
+                    .map(|value| (value, position + 2))
+                    .map_err(|error| tokens.error(&error.to_string(), tokens.span_between(position, position + 2)))
+            },
+            Some(Token::Identifier(keyword)) if TYPED_LITERAL_KEYWORDS.contains(&keyword.as_str()) => {
+                let text = match tokens.get(position + 1) {
+                    Some(Token::Text(text)) => text,
+                    _ => return Err(tokens.expected(&format!("string after {}", keyword), position + 1))
+                };
+                let value = match keyword.as_str() {
+                    "DATE" => date_time::parse_date(text).map(Value::Date),
+                    "TIMESTAMP" => date_time::parse_timestamp_or_date(text).map(Value::Timestamp),
+                    _ => Interval::parse(text).map(Value::Interval)
+                };
+                value.map(|value| (value, position + 2))
+                    .ok_or_else(|| tokens.error(&format!("invalid {} '{}'", keyword.to_lowercase(), text), tokens.span_between(position, position + 2)))
+            },
+            _ => Err(tokens.expected(description, position))
+        }
+    }
+
+    // Without an operand every branch has a condition, with an operand ("CASE column WHEN ...")
+    // the operand is compared with the value of every branch
+    fn parse_case(tokens: &Tokens, position: usize) -> Result<(Expression, usize), ParseError> {
+        let (operand, mut current_position) = if Query::is_keyword_at(tokens, position + 1, "WHEN") {
+            (None, position + 1)
+        } else {
+            let (operand, position_after_operand) = Query::parse_expression(tokens, position + 1, "operand")?;
+            (Some(operand), position_after_operand)
+        };
+        let mut conditions: Vec<Predicate> = Vec::new();
+        let mut values: Vec<Expression> = Vec::new();
+        let mut results: Vec<Expression> = Vec::new();
+        while Query::is_keyword_at(tokens, current_position, "WHEN") {
+            current_position = if operand.is_some() {
+                let (value, position_after_value) = Query::parse_expression(tokens, current_position + 1, "value to compare with")?;
+                values.push(value);
+                position_after_value
             } else {
-                Err(anyhow!(format!("Expected to find keyword PROJECT in {:?} at position {}", tokens, position)))
+                let (condition, position_after_condition) = Query::parse_predicate(tokens, current_position + 1)?;
+                conditions.push(condition);
+                position_after_condition
+            };
+            if !Query::is_keyword_at(tokens, current_position, "THEN") {
+                return Err(tokens.expected("keyword THEN", current_position));
+            }
+            let (result, position_after_result) = Query::parse_expression(tokens, current_position + 1, "result")?;
+            results.push(result);
+            current_position = position_after_result;
+        }
+        if results.is_empty() {
+            return Err(tokens.expected("keyword WHEN", current_position));
+        }
+        let otherwise = if Query::is_keyword_at(tokens, current_position, "ELSE") {
+            let (otherwise, position_after_otherwise) = Query::parse_expression(tokens, current_position + 1, "result")?;
+            current_position = position_after_otherwise;
+            Some(Box::new(otherwise))
+        } else {
+            None
+        };
+        if !Query::is_keyword_at(tokens, current_position, "END") {
+            return Err(tokens.expected("keyword END", current_position));
+        }
+        let expression = match operand {
+            Some(operand) => Expression::SimpleCase {
+                operand: Box::new(operand),
+                branches: values.into_iter().zip(results).collect(),
+                otherwise
+            },
+            None => Expression::Case {
+                branches: conditions.into_iter().zip(results).collect(),
+                otherwise
             }
+        };
+        Ok((expression, current_position + 1))
+    }
+
+    fn parse_function_call(tokens: &Tokens, position: usize) -> Result<(Expression, usize), ParseError> {
+        let function = match tokens.get(position) {
+            Some(Token::Identifier(name)) => Function::from(name),
+            _ => None
+        }.ok_or_else(|| tokens.expected("function", position))?;
+        if function == Function::Extract {
+            return Query::parse_extract(tokens, position);
+        }
+        let mut arguments: Vec<Expression> = Vec::new();
+        let mut current_position = position + 2;
+        let mut all_arguments_read = tokens.get(current_position) == Some(&Token::RightParenthesis);
+        while !all_arguments_read {
+            let (argument, position_after_argument) = Query::parse_expression(tokens, current_position, "argument of the function")?;
+            arguments.push(argument);
+            let (position_after_comma, is_followed_by_comma) = Query::skip_comma(tokens, position_after_argument);
+            current_position = position_after_comma;
+            all_arguments_read = !is_followed_by_comma;
+        }
+        if tokens.get(current_position) != Some(&Token::RightParenthesis) {
+            return Err(tokens.expected("closing parenthesis", current_position));
+        }
+        function.check_arity(arguments.len())
+            .map_err(|message| tokens.error(&message, tokens.span_between(position, current_position + 1)))?;
+        Ok((Expression::Function { function, arguments }, current_position + 1))
+    }
+
+    // EXTRACT(<field> FROM <date or timestamp>), the field becomes the first argument of the function
+    fn parse_extract(tokens: &Tokens, position: usize) -> Result<(Expression, usize), ParseError> {
+        let field = match tokens.get(position + 2) {
+            Some(Token::Identifier(field)) if date_time::FIELDS.contains(&field.to_uppercase().as_str()) => field.to_uppercase(),
+            _ => return Err(tokens.expected(&format!("field to extract, one of {}", date_time::FIELDS.join(", ")), position + 2))
+        };
+        if !Query::is_keyword_at(tokens, position + 3, "FROM") {
+            return Err(tokens.expected("keyword FROM", position + 3));
+        }
+        let (argument, position_after_argument) = Query::parse_expression(tokens, position + 4, "date or timestamp to extract from")?;
+        if tokens.get(position_after_argument) != Some(&Token::RightParenthesis) {
+            return Err(tokens.expected("closing parenthesis", position_after_argument));
+        }
+        Ok((Expression::Function {
+            function: Function::Extract,
+            arguments: vec![Expression::Literal(Value::Text(field)), argument]
+        }, position_after_argument + 1))
+    }
+
+    // The columns listed after EXCEPT take up the rest of the comma separated items
+    fn parse_all_columns(tokens: &Tokens, position: usize) -> Result<(ProjectionItem, usize), ParseError> {
+        let mut except: Vec<String> = Vec::new();
+        if !Query::is_keyword_at(tokens, position + 1, "EXCEPT") {
+            return Ok((ProjectionItem::AllColumns { except }, position + 1));
+        }
+        let mut current_position = position + 2;
+        let mut all_columns_read = false;
+        while !all_columns_read {
+            except.push(Query::parse_name(tokens, current_position, "column to exclude")?);
+            let (position_after_comma, is_followed_by_comma) = Query::skip_comma(tokens, current_position + 1);
+            current_position = position_after_comma;
+            all_columns_read = !is_followed_by_comma;
+        }
+        Ok((ProjectionItem::AllColumns { except }, current_position))
+    }
+
+    // COLUMNS('regex') selects the columns the whole name of which matches the regular expression
+    fn parse_matching_columns(tokens: &Tokens, position: usize) -> Result<Option<(Pattern, usize)>, ParseError> {
+        if !Query::is_keyword_at(tokens, position, "COLUMNS") || tokens.get(position + 1) != Some(&Token::LeftParenthesis) {
+            return Ok(None);
+        }
+        let pattern = Query::parse_pattern(tokens, position + 2, true)?;
+        if tokens.get(position + 3) != Some(&Token::RightParenthesis) {
+            return Err(tokens.expected("closing parenthesis", position + 3));
+        }
+        Ok(Some((pattern, position + 4)))
+    }
+
+    // An anchored pattern has to match the whole value rather than any part of it
+    fn parse_pattern(tokens: &Tokens, position: usize, anchored: bool) -> Result<Pattern, ParseError> {
+        match tokens.get(position) {
+            Some(Token::Text(source)) => {
+                let regex = if anchored {
+                    format!("^(?:{})$", source)
+                } else {
+                    source.to_string()
+                };
+                Pattern::new(source, &regex)
+                    .map_err(|_| tokens.error(&format!("invalid regular expression {}", Token::Text(source.to_string())), tokens.span(position)))
+            },
+            _ => Err(tokens.expected("string with a regular expression", position))
+        }
+    }
+
+    // An aggregate is only recognized when the function name is followed by an opening parenthesis,
+    // so that columns which happen to be named like an aggregate function can still be used
+    fn parse_aggregate(tokens: &Tokens, position: usize) -> Result<Option<(Aggregate, usize)>, ParseError> {
+        let function = match tokens.get(position) {
+            Some(Token::Identifier(name)) if tokens.get(position + 1) == Some(&Token::LeftParenthesis) =>
+                match AggregateFunction::from(name) {
+                    Some(function) => function,
+                    None => return Ok(None)
+                },
+            _ => return Ok(None)
+        };
+        let distinct = Query::is_keyword_at(tokens, position + 2, "DISTINCT");
+        let argument_position = if distinct { position + 3 } else { position + 2 };
+        let column_name = match tokens.get(argument_position) {
+            Some(token) if token.is_operator("*") => {
+                if function != AggregateFunction::Count || distinct {
+                    return Err(tokens.error("only COUNT can be applied to *", tokens.span_between(position + 2, argument_position + 1)));
+                }
+                None
+            },
+            _ => Some(Query::parse_name(tokens, argument_position, "argument of the aggregate function")?)
+        };
+        if tokens.get(argument_position + 1) != Some(&Token::RightParenthesis) {
+            return Err(tokens.expected("closing parenthesis", argument_position + 1));
+        }
+        Ok(Some((Aggregate {
+            function,
+            column_name,
+            distinct
+        }, argument_position + 2)))
+    }
+
+    // Returns the position after the comma and whether the comma was found
+    fn skip_comma(tokens: &Tokens, position: usize) -> (usize, bool) {
+        if tokens.get(position) == Some(&Token::Comma) {
+            (position + 1, true)
         } else {
-            Err(anyhow!(format!("Could not parse projection part in {:?} at position {}", tokens, position)))
-        }
-    }
-
-    fn parse_filter(tokens: &Vec<&str>, position: usize) -> Result<(Option<Filter>, usize), Error> {
-        if let Some(&token) = tokens.get(position) {
-            if token == "FILTER" {
-                let column = tokens.get(position + 1)
-                    .ok_or_else(|| anyhow!("Could not find column in the filter in {:?} at position {}", tokens, &position + 1))?;
-                let filter_type = FilterType::from(tokens.get(position + 2)
-                    .ok_or_else(|| anyhow!("Could not find operator '>' or '=' in the filter in {:?} at position {}", tokens, &position))?)
-                    .context(format!("Unknown filter operator in {:?} at position {}", tokens, &position + 2))?;
-                let value_input = tokens.get(position + 3).map(|value| value.trim_matches('"'));
-                let value = Value::parse_value(value_input
-                    .ok_or_else(|| anyhow!("Could not find value to filter by in the filter in {:?} at position {}", tokens, &position + 3))?.to_string())?;
-                Ok((Some(Filter {
-                    column_name: column.to_string(),
-                    filter_type,
-                    value
-                }), position + 4))
+            (position, false)
+        }
+    }
+
+    fn is_clause_keyword(token: &Token) -> bool {
+        CLAUSE_KEYWORDS.iter().any(|keyword| token.is_keyword(keyword))
+    }
+
+    fn is_keyword_at(tokens: &Tokens, position: usize, keyword: &str) -> bool {
+        tokens.get(position).is_some_and(|token| token.is_keyword(keyword))
+    }
+
+    // A name of a table or of a column, the clause keywords can only be used as names when quoted
+    fn parse_name(tokens: &Tokens, position: usize, description: &str) -> Result<String, ParseError> {
+        match tokens.get(position) {
+            Some(token @ Token::Identifier(name)) if !Query::is_clause_keyword(token) => Ok(name.to_string()),
+            Some(Token::QuotedIdentifier(name)) => Ok(name.to_string()),
+            _ => Err(tokens.expected(description, position))
+        }
+    }
+
+    fn parse_from(tokens: &Tokens, position: usize) -> Result<(Option<String>, usize), ParseError> {
+        if Query::is_keyword_at(tokens, position, "FROM") {
+            let table_name = Query::parse_name(tokens, position + 1, "table name")?;
+            Ok((Some(table_name), position + 2))
+        } else {
+            Ok((None, position))
+        }
+    }
+
+    fn parse_joins(tokens: &Tokens, position: usize) -> Result<(Vec<Join>, usize), ParseError> {
+        let mut joins: Vec<Join> = Vec::new();
+        let mut current_position = position;
+        loop {
+            let (join_type, join_keyword_position) = if Query::is_keyword_at(tokens, current_position, "JOIN") {
+                (JoinType::Inner, current_position)
+            } else if Query::is_keyword_at(tokens, current_position, "INNER") {
+                (JoinType::Inner, current_position + 1)
+            } else if Query::is_keyword_at(tokens, current_position, "LEFT") {
+                if Query::is_keyword_at(tokens, current_position + 1, "OUTER") {
+                    (JoinType::LeftOuter, current_position + 2)
+                } else {
+                    (JoinType::LeftOuter, current_position + 1)
+                }
             } else {
-                Err(anyhow!(format!("Expected to find keyword FILTER in {:?} at position {}", tokens, position)))
+                return Ok((joins, current_position));
+            };
+            if !Query::is_keyword_at(tokens, join_keyword_position, "JOIN") {
+                return Err(tokens.expected("keyword JOIN", join_keyword_position));
             }
+            let table_name = Query::parse_name(tokens, join_keyword_position + 1, "table name")?;
+            if !Query::is_keyword_at(tokens, join_keyword_position + 2, "ON") {
+                return Err(tokens.expected("keyword ON", join_keyword_position + 2));
+            }
+            let left_column_name = Query::parse_name(tokens, join_keyword_position + 3, "column to join on")?;
+            if !tokens.get(join_keyword_position + 4).is_some_and(|token| token.is_operator("=")) {
+                return Err(tokens.expected("operator =", join_keyword_position + 4));
+            }
+            let right_column_name = Query::parse_name(tokens, join_keyword_position + 5, "column to join on")?;
+            joins.push(Join {
+                join_type,
+                table_name,
+                left_column_name,
+                right_column_name
+            });
+            current_position = join_keyword_position + 6;
+        }
+    }
+
+    fn parse_filter(tokens: &Tokens, position: usize, dialect: Dialect) -> Result<(Option<Predicate>, usize), ParseError> {
+        if let Some(token) = tokens.get(position) {
+            if token.is_keyword(dialect.filter_keyword()) {
+                let (predicate, position_after_predicate) = Query::parse_predicate(tokens, position + 1)?;
+                if let Some(aggregate) = predicate.aggregates().first() {
+                    return Err(tokens.error(
+                        &format!("aggregate {} cannot be used in {}, aggregated rows can be filtered with HAVING", aggregate, dialect.filter_keyword()),
+                        tokens.span_between(position + 1, position_after_predicate)));
+                }
+                Ok((Some(predicate), position_after_predicate))
+            } else if Query::is_clause_keyword(token) && !token.is_keyword(Dialect::Native.filter_keyword()) && !token.is_keyword(Dialect::Sql.filter_keyword()) {
+                Ok((None, position))
+            } else {
+                Err(tokens.expected(&format!("keyword {}", dialect.filter_keyword()), position))
+            }
+        } else {
+            Ok((None, position))
+        }
+    }
+
+    fn parse_group_by(tokens: &Tokens, position: usize) -> Result<(Vec<Expression>, usize), ParseError> {
+        let mut group_by: Vec<Expression> = Vec::new();
+        if !Query::is_keyword_at(tokens, position, "GROUP") {
+            return Ok((group_by, position));
+        }
+        if !Query::is_keyword_at(tokens, position + 1, "BY") {
+            return Err(tokens.expected("keyword BY", position + 1));
+        }
+        let mut current_position = position + 2;
+        let mut all_columns_read = false;
+        while !all_columns_read {
+            let (expression, position_after_expression) = Query::parse_expression(tokens, current_position, "column to group by")?;
+            group_by.push(expression);
+            let (position_after_comma, is_followed_by_comma) = Query::skip_comma(tokens, position_after_expression);
+            current_position = position_after_comma;
+            all_columns_read = !is_followed_by_comma;
+        }
+        Ok((group_by, current_position))
+    }
+
+    fn parse_having(tokens: &Tokens, position: usize) -> Result<(Option<Predicate>, usize), ParseError> {
+        if Query::is_keyword_at(tokens, position, "HAVING") {
+            let (predicate, position_after_predicate) = Query::parse_predicate(tokens, position + 1)?;
+            Ok((Some(predicate), position_after_predicate))
         } else {
             Ok((None, position))
         }
     }
-}
-
-#[derive(Debug, PartialEq)]
-pub struct Filter {
-    pub column_name: String,
-    pub value: Value,
-    pub filter_type: FilterType
-}
 
-#[derive(Debug, PartialEq)]
-pub enum FilterType {
-    Greater,
-    Equal
-}
+    fn parse_order_by(tokens: &Tokens, position: usize) -> Result<(Vec<OrderBy>, usize), ParseError> {
+        let mut order_by: Vec<OrderBy> = Vec::new();
+        if !Query::is_keyword_at(tokens, position, "ORDER") {
+            return Ok((order_by, position));
+        }
+        if !Query::is_keyword_at(tokens, position + 1, "BY") {
+            return Err(tokens.expected("keyword BY", position + 1));
+        }
+        let mut current_position = position + 2;
+        let mut all_keys_read = false;
+        while !all_keys_read {
+            let (expression, position_after_expression) = Query::parse_expression(tokens, current_position, "column to order by")?;
+            current_position = position_after_expression;
+            let mut direction = SortDirection::Ascending;
+            if Query::is_keyword_at(tokens, current_position, "ASC") {
+                current_position += 1;
+            } else if Query::is_keyword_at(tokens, current_position, "DESC") {
+                direction = SortDirection::Descending;
+                current_position += 1;
+            }
+            // NULL is the smallest value, unless the position of NULLs is given explicitly
+            let mut nulls = if direction == SortDirection::Ascending { NullsOrder::First } else { NullsOrder::Last };
+            if Query::is_keyword_at(tokens, current_position, "NULLS") {
+                nulls = if Query::is_keyword_at(tokens, current_position + 1, "FIRST") {
+                    NullsOrder::First
+                } else if Query::is_keyword_at(tokens, current_position + 1, "LAST") {
+                    NullsOrder::Last
+                } else {
+                    return Err(tokens.expected("keyword FIRST or LAST", current_position + 1));
+                };
+                current_position += 2;
+            }
+            order_by.push(OrderBy {
+                expression,
+                direction,
+                nulls
+            });
+            let (position_after_comma, is_followed_by_comma) = Query::skip_comma(tokens, current_position);
+            current_position = position_after_comma;
+            all_keys_read = !is_followed_by_comma;
+        }
+        Ok((order_by, current_position))
+    }
+
+    fn parse_limit(tokens: &Tokens, position: usize) -> Result<(Option<Limit>, usize), ParseError> {
+        if !Query::is_keyword_at(tokens, position, "LIMIT") {
+            return Ok((None, position));
+        }
+        let count = Query::parse_row_count(tokens, position + 1)?;
+        if Query::is_keyword_at(tokens, position + 2, "OFFSET") {
+            let offset = Query::parse_row_count(tokens, position + 3)?;
+            Ok((Some(Limit { count, offset }), position + 4))
+        } else {
+            Ok((Some(Limit { count, offset: 0 }), position + 2))
+        }
+    }
+
+    fn parse_row_count(tokens: &Tokens, position: usize) -> Result<usize, ParseError> {
+        match tokens.get(position) {
+            Some(Token::Number(number)) => number.parse::<usize>().ok(),
+            _ => None
+        }.ok_or_else(|| tokens.expected("a non-negative number of rows", position))
+    }
+
+    // Precedence from the loosest to the tightest binding: OR, AND, NOT
+    fn parse_predicate(tokens: &Tokens, position: usize) -> Result<(Predicate, usize), ParseError> {
+        let (mut predicate, mut current_position) = Query::parse_conjunction(tokens, position)?;
+        while Query::is_keyword_at(tokens, current_position, "OR") {
+            let (right, position_after_right) = Query::parse_conjunction(tokens, current_position + 1)?;
+            predicate = Predicate::Or(Box::new(predicate), Box::new(right));
+            current_position = position_after_right;
+        }
+        Ok((predicate, current_position))
+    }
+
+    fn parse_conjunction(tokens: &Tokens, position: usize) -> Result<(Predicate, usize), ParseError> {
+        let (mut predicate, mut current_position) = Query::parse_negation(tokens, position)?;
+        while Query::is_keyword_at(tokens, current_position, "AND") {
+            let (right, position_after_right) = Query::parse_negation(tokens, current_position + 1)?;
+            predicate = Predicate::And(Box::new(predicate), Box::new(right));
+            current_position = position_after_right;
+        }
+        Ok((predicate, current_position))
+    }
+
+    fn parse_negation(tokens: &Tokens, position: usize) -> Result<(Predicate, usize), ParseError> {
+        if Query::is_keyword_at(tokens, position, "NOT") {
+            let (negated, position_after_negated) = Query::parse_negation(tokens, position + 1)?;
+            Ok((Predicate::Not(Box::new(negated)), position_after_negated))
+        } else {
+            Query::parse_primary_predicate(tokens, position)
+        }
+    }
+
+    // A parenthesis either groups predicates or is a part of the compared expression, as in
+    // "(a + b) > 1", when neither can be parsed the error found further in the query is reported
+    fn parse_primary_predicate(tokens: &Tokens, position: usize) -> Result<(Predicate, usize), ParseError> {
+        if tokens.get(position) == Some(&Token::LeftParenthesis) {
+            match Query::parse_parenthesized_predicate(tokens, position) {
+                Ok(result) => Ok(result),
+                Err(predicate_error) => Query::parse_comparison(tokens, position)
+                    .map_err(|comparison_error| if comparison_error.span.start > predicate_error.span.start { comparison_error } else { predicate_error })
+            }
+        } else {
+            Query::parse_comparison(tokens, position)
+        }
+    }
+
+    fn parse_parenthesized_predicate(tokens: &Tokens, position: usize) -> Result<(Predicate, usize), ParseError> {
+        let (predicate, position_after_predicate) = Query::parse_predicate(tokens, position + 1)?;
+        if tokens.get(position_after_predicate) == Some(&Token::RightParenthesis) {
+            Ok((predicate, position_after_predicate + 1))
+        } else {
+            Err(tokens.expected("closing parenthesis", position_after_predicate))
+        }
+    }
+
+    fn parse_null_check(tokens: &Tokens, operand: Expression, position: usize) -> Result<(Predicate, usize), ParseError> {
+        let (negated, null_position) = if Query::is_keyword_at(tokens, position + 1, "NOT") {
+            (true, position + 2)
+        } else {
+            (false, position + 1)
+        };
+        if Query::is_keyword_at(tokens, null_position, "NULL") {
+            Ok((Predicate::IsNull {
+                operand,
+                negated
+            }, null_position + 1))
+        } else {
+            Err(tokens.expected("keyword NULL", null_position))
+        }
+    }
+
+    // A column compared with a literal is kept as a filter, so that it can be answered using the index of the column
+    fn parse_comparison(tokens: &Tokens, position: usize) -> Result<(Predicate, usize), ParseError> {
+        let (left, position_after_left) = Query::parse_expression(tokens, position, "column in the filter")?;
+        let (negated, keyword_position) = if Query::is_keyword_at(tokens, position_after_left, "NOT") {
+            (true, position_after_left + 1)
+        } else {
+            (false, position_after_left)
+        };
+        if let Some(keyword) = ["IS", "IN", "BETWEEN"].into_iter().find(|keyword| Query::is_keyword_at(tokens, keyword_position, keyword)) {
+            if !matches!(left, Expression::Column(_) | Expression::Aggregate(_)) {
+                return Err(tokens.error(&format!("only a column or an aggregate can be used with {}", keyword), tokens.span_between(position, position_after_left)));
+            }
+            return match keyword {
+                "IN" => Query::parse_in_list(tokens, left, negated, keyword_position),
+                "BETWEEN" => Query::parse_between(tokens, left, negated, keyword_position),
+                _ if !negated => Query::parse_null_check(tokens, left, keyword_position),
+                _ => Err(tokens.expected("keyword IN, BETWEEN, LIKE or ILIKE", keyword_position))
+            };
+        }
+        if let Some(Token::Operator(operator)) = tokens.get(position_after_left).filter(|_| !negated) {
+            if operator == "~" || operator == "!~" {
+                let pattern = Query::parse_pattern(tokens, position_after_left + 1, false)?;
+                return Ok((Predicate::RegexMatch {
+                    left,
+                    pattern,
+                    negated: operator == "!~"
+                }, position_after_left + 2));
+            }
+        }
+        let pattern_filter_type = [("LIKE", FilterType::Like), ("ILIKE", FilterType::ILike)].into_iter()
+            .find(|(keyword, _)| Query::is_keyword_at(tokens, keyword_position, keyword))
+            .map(|(_, filter_type)| filter_type);
+        let is_condition = matches!(&left, Expression::Function { function, .. } if function.is_condition());
+        let is_followed_by_operator = matches!(tokens.get(position_after_left), Some(Token::Operator(_)));
+        if is_condition && !negated && pattern_filter_type.is_none() && !is_followed_by_operator {
+            return Ok((Predicate::Expression(left), position_after_left));
+        }
+        let (filter_type, operator_position) = match pattern_filter_type {
+            Some(filter_type) => (filter_type, keyword_position),
+            None => (match tokens.get(position_after_left) {
+                Some(Token::Operator(operator)) => FilterType::from(operator).ok(),
+                Some(_) => None,
+                None => None
+            }.ok_or_else(|| tokens.expected("comparison operator", position_after_left))?, position_after_left)
+        };
+        let (right, position_after_right) = Query::parse_expression(tokens, operator_position + 1, "value to compare with")?;
+        let predicate = match (left, right) {
+            (Expression::Column(column_name), Expression::Literal(value)) if !value.is_null() =>
+                Predicate::Filter(Filter { column_name, value, filter_type }),
+            (Expression::Literal(value), Expression::Column(column_name)) if !value.is_null() && !filter_type.is_pattern_match() =>
+                Predicate::Filter(Filter { column_name, value, filter_type: filter_type.flipped() }),
+            (left, right) =>
+                Predicate::Comparison { left, filter_type, right }
+        };
+        if negated {
+            Ok((Predicate::Not(Box::new(predicate)), position_after_right))
+        } else {
+            Ok((predicate, position_after_right))
+        }
+    }
+
+    fn parse_in_list(tokens: &Tokens, operand: Expression, negated: bool, position: usize) -> Result<(Predicate, usize), ParseError> {
+        if tokens.get(position + 1) != Some(&Token::LeftParenthesis) {
+            return Err(tokens.expected("opening parenthesis", position + 1));
+        }
+        let mut values: Vec<Value> = Vec::new();
+        let mut current_position = position + 2;
+        let mut all_values_read = false;
+        while !all_values_read {
+            let (value, position_after_value) = Query::parse_value(tokens, current_position, "string or number in the list")?;
+            values.push(value);
+            let (position_after_comma, is_followed_by_comma) = Query::skip_comma(tokens, position_after_value);
+            current_position = position_after_comma;
+            all_values_read = !is_followed_by_comma;
+        }
+        if tokens.get(current_position) != Some(&Token::RightParenthesis) {
+            return Err(tokens.expected("closing parenthesis", current_position));
+        }
+        Ok((Predicate::In {
+            operand,
+            values,
+            negated
+        }, current_position + 1))
+    }
+
+    // Both bounds are included in the range
+    fn parse_between(tokens: &Tokens, operand: Expression, negated: bool, position: usize) -> Result<(Predicate, usize), ParseError> {
+        let (low, position_after_low) = Query::parse_value(tokens, position + 1, "lower bound of the range")?;
+        if !Query::is_keyword_at(tokens, position_after_low, "AND") {
+            return Err(tokens.expected("keyword AND", position_after_low));
+        }
+        let (high, position_after_high) = Query::parse_value(tokens, position_after_low + 1, "upper bound of the range")?;
+        Ok((Predicate::Between {
+            operand,
+            low,
+            high,
+            negated
+        }, position_after_high))
+    }
+
+    // Aggregates in HAVING are resolved against the projection, as only the projected aggregates are computed
+    fn resolve_having(&self, having: &Predicate) -> Result<(), String> {
+        for aggregate in having.aggregates().into_iter() {
+            if !self.projection.iter().any(|item| matches!(item.unaliased(), ProjectionItem::Aggregate(projected) if projected == aggregate)) {
+                return Err(format!("aggregate {} used in HAVING must appear in the projection", aggregate));
+            }
+        }
+        for column_name in having.column_names().into_iter() {
+            if !self.group_by.iter().any(|group_expression| matches!(group_expression, Expression::Column(group_column) if group_column == column_name)) {
+                return Err(format!("column {} used in HAVING must appear in GROUP BY", column_name));
+            }
+        }
+        Ok(())
+    }
+
+    // Produces the same query with every column name replaced, including the column names used
+    // as arguments of aggregates, which is used to resolve qualified and unqualified column names
+    pub(crate) fn map_column_names(&self, rename: &dyn Fn(&str) -> Result<String, Error>) -> Result<Query, Error> {
+        let mut projection: Vec<ProjectionItem> = Vec::new();
+        for item in self.projection.iter() {
+            projection.push(item.map_column_names(rename)?);
+        }
+        let mut joins: Vec<Join> = Vec::new();
+        for join in self.joins.iter() {
+            joins.push(Join {
+                left_column_name: rename(&join.left_column_name)?,
+                right_column_name: rename(&join.right_column_name)?,
+                ..join.clone()
+            });
+        }
+        let mut order_by: Vec<OrderBy> = Vec::new();
+        for key in self.order_by.iter() {
+            order_by.push(OrderBy {
+                expression: key.expression.map_column_names(rename)?,
+                ..key.clone()
+            });
+        }
+        Ok(Query {
+            distinct: self.distinct,
+            projection,
+            from: self.from.clone(),
+            joins,
+            filter: self.filter.as_ref().map(|filter| filter.map_column_names(rename)).transpose()?,
+            group_by: self.group_by.iter().map(|expression| expression.map_column_names(rename)).collect::<Result<Vec<Expression>, Error>>()?,
+            having: self.having.as_ref().map(|having| having.map_column_names(rename)).transpose()?,
+            order_by,
+            limit: self.limit.clone()
+        })
+    }
+}
+
+// All the columns and the columns matching a pattern are expanded against the columns of the
+// queried table only when the query is executed. A plain column is kept apart from the
+// computed expressions, as it can be copied from the row or looked up in an index.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ProjectionItem {
+    Column(String),
+    AllColumns {
+        except: Vec<String>
+    },
+    MatchingColumns(Pattern),
+    Aggregate(Aggregate),
+    Expression(Expression),
+    Aliased {
+        item: Box<ProjectionItem>,
+        alias: String
+    }
+}
+
+impl ProjectionItem {
+    pub fn unaliased(&self) -> &ProjectionItem {
+        match self {
+            ProjectionItem::Aliased { item, .. } => item.unaliased(),
+            item => item
+        }
+    }
+
+    // The name of the projected value in the result, which is the alias when there is one
+    pub fn name(&self) -> String {
+        match self {
+            ProjectionItem::Aliased { alias, .. } => alias.to_string(),
+            item => item.to_string()
+        }
+    }
+
+    fn map_column_names(&self, rename: &dyn Fn(&str) -> Result<String, Error>) -> Result<ProjectionItem, Error> {
+        Ok(match self {
+            ProjectionItem::Column(column_name) =>
+                ProjectionItem::Column(rename(column_name)?),
+            ProjectionItem::AllColumns { except } =>
+                ProjectionItem::AllColumns {
+                    except: except.iter().map(|column_name| rename(column_name)).collect::<Result<Vec<String>, Error>>()?
+                },
+            ProjectionItem::MatchingColumns(pattern) =>
+                ProjectionItem::MatchingColumns(pattern.clone()),
+            ProjectionItem::Aggregate(aggregate) =>
+                ProjectionItem::Aggregate(aggregate.map_column_names(rename)?),
+            ProjectionItem::Expression(expression) =>
+                ProjectionItem::Expression(expression.map_column_names(rename)?),
+            ProjectionItem::Aliased { item, alias } =>
+                ProjectionItem::Aliased {
+                    item: Box::new(item.map_column_names(rename)?),
+                    alias: alias.to_string()
+                }
+        })
+    }
+}
+
+impl fmt::Display for ProjectionItem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProjectionItem::Column(column_name) =>
+                write!(f, "{}", column_name),
+            ProjectionItem::AllColumns { except } if except.is_empty() =>
+                write!(f, "*"),
+            ProjectionItem::AllColumns { except } =>
+                write!(f, "* EXCEPT {}", except.join(", ")),
+            ProjectionItem::MatchingColumns(pattern) =>
+                write!(f, "COLUMNS({})", pattern),
+            ProjectionItem::Aggregate(aggregate) =>
+                write!(f, "{}", aggregate),
+            ProjectionItem::Expression(expression) =>
+                write!(f, "{}", expression),
+            ProjectionItem::Aliased { item, alias } =>
+                write!(f, "{} AS {}", item, alias)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expression {
+    Column(String),
+    Literal(Value),
+    Negation(Box<Expression>),
+    Binary {
+        operator: BinaryOperator,
+        left: Box<Expression>,
+        right: Box<Expression>
+    },
+    Function {
+        function: Function,
+        arguments: Vec<Expression>
+    },
+    // The result of the first branch with a true condition, or of ELSE (NULL when absent)
+    Case {
+        branches: Vec<(Predicate, Expression)>,
+        otherwise: Option<Box<Expression>>
+    },
+    // The result of the first branch with a value equal to the operand
+    SimpleCase {
+        operand: Box<Expression>,
+        branches: Vec<(Expression, Expression)>,
+        otherwise: Option<Box<Expression>>
+    },
+    // Only used with the aggregated rows, in HAVING and ORDER BY
+    Aggregate(Aggregate)
+}
+
+impl Expression {
+    pub(crate) fn map_column_names(&self, rename: &dyn Fn(&str) -> Result<String, Error>) -> Result<Expression, Error> {
+        Ok(match self {
+            Expression::Column(column_name) => Expression::Column(rename(column_name)?),
+            Expression::Literal(value) => Expression::Literal(value.clone()),
+            Expression::Negation(negated) => Expression::Negation(Box::new(negated.map_column_names(rename)?)),
+            Expression::Binary { operator, left, right } => Expression::Binary {
+                operator: *operator,
+                left: Box::new(left.map_column_names(rename)?),
+                right: Box::new(right.map_column_names(rename)?)
+            },
+            Expression::Function { function, arguments } => Expression::Function {
+                function: *function,
+                arguments: arguments.iter().map(|argument| argument.map_column_names(rename)).collect::<Result<Vec<Expression>, Error>>()?
+            },
+            Expression::Case { branches, otherwise } => Expression::Case {
+                branches: branches.iter()
+                    .map(|(condition, result)| Ok((condition.map_column_names(rename)?, result.map_column_names(rename)?)))
+                    .collect::<Result<Vec<(Predicate, Expression)>, Error>>()?,
+                otherwise: otherwise.as_ref().map(|otherwise| otherwise.map_column_names(rename).map(Box::new)).transpose()?
+            },
+            Expression::SimpleCase { operand, branches, otherwise } => Expression::SimpleCase {
+                operand: Box::new(operand.map_column_names(rename)?),
+                branches: branches.iter()
+                    .map(|(value, result)| Ok((value.map_column_names(rename)?, result.map_column_names(rename)?)))
+                    .collect::<Result<Vec<(Expression, Expression)>, Error>>()?,
+                otherwise: otherwise.as_ref().map(|otherwise| otherwise.map_column_names(rename).map(Box::new)).transpose()?
+            },
+            Expression::Aggregate(aggregate) => Expression::Aggregate(aggregate.map_column_names(rename)?)
+        })
+    }
+
+    // The argument of an aggregate is not a column of the aggregated rows, so it is not included
+    pub(crate) fn column_names(&self) -> Vec<&str> {
+        match self {
+            Expression::Column(column_name) => vec![column_name.as_str()],
+            Expression::Literal(_) => Vec::new(),
+            Expression::Negation(negated) => negated.column_names(),
+            Expression::Binary { left, right, .. } => left.column_names().into_iter().chain(right.column_names()).collect(),
+            Expression::Function { arguments, .. } => arguments.iter().flat_map(|argument| argument.column_names()).collect(),
+            Expression::Case { branches, otherwise } => branches.iter()
+                .flat_map(|(condition, result)| condition.column_names().into_iter().chain(result.column_names()))
+                .chain(otherwise.iter().flat_map(|otherwise| otherwise.column_names()))
+                .collect(),
+            Expression::SimpleCase { operand, branches, otherwise } => operand.column_names().into_iter()
+                .chain(branches.iter().flat_map(|(value, result)| value.column_names().into_iter().chain(result.column_names())))
+                .chain(otherwise.iter().flat_map(|otherwise| otherwise.column_names()))
+                .collect(),
+            Expression::Aggregate(_) => Vec::new()
+        }
+    }
+
+    pub(crate) fn aggregates(&self) -> Vec<&Aggregate> {
+        match self {
+            Expression::Column(_) | Expression::Literal(_) => Vec::new(),
+            Expression::Negation(negated) => negated.aggregates(),
+            Expression::Binary { left, right, .. } => left.aggregates().into_iter().chain(right.aggregates()).collect(),
+            Expression::Function { arguments, .. } => arguments.iter().flat_map(|argument| argument.aggregates()).collect(),
+            Expression::Case { branches, otherwise } => branches.iter()
+                .flat_map(|(condition, result)| condition.aggregates().into_iter().chain(result.aggregates()))
+                .chain(otherwise.iter().flat_map(|otherwise| otherwise.aggregates()))
+                .collect(),
+            Expression::SimpleCase { operand, branches, otherwise } => operand.aggregates().into_iter()
+                .chain(branches.iter().flat_map(|(value, result)| value.aggregates().into_iter().chain(result.aggregates())))
+                .chain(otherwise.iter().flat_map(|otherwise| otherwise.aggregates()))
+                .collect(),
+            Expression::Aggregate(aggregate) => vec![aggregate]
+        }
+    }
+
+    // Operands binding looser than the operator they are used with have to be parenthesized
+    fn to_operand_string(&self, precedence: u8) -> String {
+        match self {
+            Expression::Binary { operator, .. } if operator.precedence() < precedence => format!("({})", self),
+            expression => expression.to_string()
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expression::Column(column_name) =>
+                write!(f, "{}", column_name),
+            Expression::Literal(value) =>
+                write!(f, "{}", format_literal(value)),
+            Expression::Negation(negated) =>
+                write!(f, "-{}", negated.to_operand_string(u8::MAX)),
+            Expression::Binary { operator, left, right } =>
+                write!(f, "{} {} {}", left.to_operand_string(operator.precedence()), operator, right.to_operand_string(operator.precedence() + 1)),
+            Expression::Function { function: Function::Extract, arguments } if arguments.len() == 2 =>
+                write!(f, "EXTRACT({} FROM {})", match &arguments[0] {
+                    Expression::Literal(Value::Text(field)) => field.to_string(),
+                    field => field.to_string()
+                }, arguments[1]),
+            Expression::Function { function, arguments } =>
+                write!(f, "{}({})", function, arguments.iter().map(|argument| argument.to_string()).collect::<Vec<String>>().join(", ")),
+            Expression::Case { branches, otherwise } => {
+                write!(f, "CASE")?;
+                for (condition, result) in branches.iter() {
+                    write!(f, " WHEN {} THEN {}", condition, result)?;
+                }
+                format_case_end(f, otherwise)
+            },
+            Expression::Aggregate(aggregate) =>
+                write!(f, "{}", aggregate),
+            Expression::SimpleCase { operand, branches, otherwise } => {
+                write!(f, "CASE {}", operand)?;
+                for (value, result) in branches.iter() {
+                    write!(f, " WHEN {} THEN {}", value, result)?;
+                }
+                format_case_end(f, otherwise)
+            }
+        }
+    }
+}
+
+fn format_case_end(f: &mut fmt::Formatter, otherwise: &Option<Box<Expression>>) -> fmt::Result {
+    if let Some(otherwise) = otherwise {
+        write!(f, " ELSE {}", otherwise)?;
+    }
+    write!(f, " END")
+}
+
+// Strings are quoted the same way as in the query, dates, timestamps and intervals are preceded by their types
+fn format_literal(value: &Value) -> String {
+    match value {
+        Value::Text(text) => Token::Text(text.to_string()).to_string(),
+        Value::Date(_) => format!("DATE '{}'", value),
+        Value::Timestamp(_) => format!("TIMESTAMP '{}'", value),
+        Value::Interval(_) => format!("INTERVAL '{}'", value),
+        value => value.to_string()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Concatenate
+}
+
+impl BinaryOperator {
+    fn from(token: &Token) -> Option<BinaryOperator> {
+        match token {
+            Token::Operator(operator) => match operator.as_str() {
+                "+" => Some(BinaryOperator::Add),
+                "-" => Some(BinaryOperator::Subtract),
+                "*" => Some(BinaryOperator::Multiply),
+                "/" => Some(BinaryOperator::Divide),
+                "||" => Some(BinaryOperator::Concatenate),
+                _ => None
+            },
+            _ => None
+        }
+    }
+
+    // Concatenation binds looser than the arithmetic operators, so that numbers can be computed before being concatenated
+    fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Concatenate => 1,
+            BinaryOperator::Add | BinaryOperator::Subtract => 2,
+            BinaryOperator::Multiply | BinaryOperator::Divide => 3
+        }
+    }
+}
+
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Concatenate => "||"
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+// A regular expression compiled once when the query is parsed, patterns are compared by their source
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    source: String,
+    regex: Regex
+}
+
+impl Pattern {
+    fn new(source: &str, regex: &str) -> Result<Pattern, regex::Error> {
+        Ok(Pattern {
+            source: source.to_string(),
+            regex: Regex::new(regex)?
+        })
+    }
+
+    pub fn is_match(&self, input: &str) -> bool {
+        self.regex.is_match(input)
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Token::Text(self.source.to_string()))
+    }
+}
+
+// The argument of an aggregate is absent for COUNT(*), a distinct aggregate
+// only takes every distinct value of the argument into account once
+#[derive(Debug, PartialEq, Clone)]
+pub struct Aggregate {
+    pub function: AggregateFunction,
+    pub column_name: Option<String>,
+    pub distinct: bool
+}
+
+impl Aggregate {
+    fn map_column_names(&self, rename: &dyn Fn(&str) -> Result<String, Error>) -> Result<Aggregate, Error> {
+        Ok(Aggregate {
+            function: self.function,
+            column_name: self.column_name.as_deref().map(rename).transpose()?,
+            distinct: self.distinct
+        })
+    }
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let distinct = if self.distinct { "DISTINCT " } else { "" };
+        write!(f, "{}({}{})", self.function, distinct, self.column_name.as_deref().unwrap_or("*"))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Min,
+    Max,
+    Avg
+}
+
+impl fmt::Display for AggregateFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            AggregateFunction::Count => "COUNT",
+            AggregateFunction::Sum => "SUM",
+            AggregateFunction::Min => "MIN",
+            AggregateFunction::Max => "MAX",
+            AggregateFunction::Avg => "AVG"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl AggregateFunction {
+    fn from(input: &str) -> Option<AggregateFunction> {
+        match input {
+            "COUNT" => Some(AggregateFunction::Count),
+            "SUM" => Some(AggregateFunction::Sum),
+            "MIN" => Some(AggregateFunction::Min),
+            "MAX" => Some(AggregateFunction::Max),
+            "AVG" => Some(AggregateFunction::Avg),
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Join {
+    pub join_type: JoinType,
+    pub table_name: String,
+    pub left_column_name: String,
+    pub right_column_name: String
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum JoinType {
+    Inner,
+    LeftOuter
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct OrderBy {
+    pub expression: Expression,
+    pub direction: SortDirection,
+    pub nulls: NullsOrder
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Limit {
+    pub count: usize,
+    pub offset: usize
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SortDirection {
+    Ascending,
+    Descending
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum NullsOrder {
+    First,
+    Last
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Predicate {
+    Filter(Filter),
+    Comparison {
+        left: Expression,
+        filter_type: FilterType,
+        right: Expression
+    },
+    // The operand of IS NULL, IN and BETWEEN is a column or, in HAVING, an aggregate
+    IsNull {
+        operand: Expression,
+        negated: bool
+    },
+    RegexMatch {
+        left: Expression,
+        pattern: Pattern,
+        negated: bool
+    },
+    // A function which is a condition, such as STARTS_WITH, used on its own
+    Expression(Expression),
+    In {
+        operand: Expression,
+        values: Vec<Value>,
+        negated: bool
+    },
+    Between {
+        operand: Expression,
+        low: Value,
+        high: Value,
+        negated: bool
+    },
+    And(Box<Predicate>, Box<Predicate>),
+    Or(Box<Predicate>, Box<Predicate>),
+    Not(Box<Predicate>)
+}
+
+impl Predicate {
+    pub(crate) fn map_column_names(&self, rename: &dyn Fn(&str) -> Result<String, Error>) -> Result<Predicate, Error> {
+        Ok(match self {
+            Predicate::Filter(filter) => Predicate::Filter(Filter {
+                column_name: rename(&filter.column_name)?,
+                ..filter.clone()
+            }),
+            Predicate::Comparison { left, filter_type, right } => Predicate::Comparison {
+                left: left.map_column_names(rename)?,
+                filter_type: filter_type.clone(),
+                right: right.map_column_names(rename)?
+            },
+            Predicate::IsNull { operand, negated } => Predicate::IsNull {
+                operand: operand.map_column_names(rename)?,
+                negated: *negated
+            },
+            Predicate::RegexMatch { left, pattern, negated } => Predicate::RegexMatch {
+                left: left.map_column_names(rename)?,
+                pattern: pattern.clone(),
+                negated: *negated
+            },
+            Predicate::Expression(expression) => Predicate::Expression(expression.map_column_names(rename)?),
+            Predicate::In { operand, values, negated } => Predicate::In {
+                operand: operand.map_column_names(rename)?,
+                values: values.clone(),
+                negated: *negated
+            },
+            Predicate::Between { operand, low, high, negated } => Predicate::Between {
+                operand: operand.map_column_names(rename)?,
+                low: low.clone(),
+                high: high.clone(),
+                negated: *negated
+            },
+            Predicate::And(left, right) =>
+                Predicate::And(Box::new(left.map_column_names(rename)?), Box::new(right.map_column_names(rename)?)),
+            Predicate::Or(left, right) =>
+                Predicate::Or(Box::new(left.map_column_names(rename)?), Box::new(right.map_column_names(rename)?)),
+            Predicate::Not(negated) =>
+                Predicate::Not(Box::new(negated.map_column_names(rename)?))
+        })
+    }
+
+    // Splits the predicate into the conditions which all have to be true for the predicate to be true
+    pub(crate) fn conjuncts(&self) -> Vec<&Predicate> {
+        match self {
+            Predicate::And(left, right) => left.conjuncts().into_iter().chain(right.conjuncts()).collect(),
+            predicate => vec![predicate]
+        }
+    }
+
+    pub(crate) fn conjunction(predicates: Vec<Predicate>) -> Option<Predicate> {
+        predicates.into_iter().reduce(|left, right| Predicate::And(Box::new(left), Box::new(right)))
+    }
+
+    pub(crate) fn column_names(&self) -> Vec<&str> {
+        match self {
+            Predicate::Filter(filter) => vec![filter.column_name.as_str()],
+            Predicate::Comparison { left, right, .. } => left.column_names().into_iter().chain(right.column_names()).collect(),
+            Predicate::RegexMatch { left, .. } | Predicate::Expression(left) => left.column_names(),
+            Predicate::IsNull { operand, .. } | Predicate::In { operand, .. } | Predicate::Between { operand, .. } =>
+                operand.column_names(),
+            Predicate::And(left, right) | Predicate::Or(left, right) =>
+                left.column_names().into_iter().chain(right.column_names()).collect(),
+            Predicate::Not(negated) => negated.column_names()
+        }
+    }
+
+    pub(crate) fn aggregates(&self) -> Vec<&Aggregate> {
+        match self {
+            Predicate::Filter(_) => Vec::new(),
+            Predicate::Comparison { left, right, .. } => left.aggregates().into_iter().chain(right.aggregates()).collect(),
+            Predicate::RegexMatch { left, .. } | Predicate::Expression(left) => left.aggregates(),
+            Predicate::IsNull { operand, .. } | Predicate::In { operand, .. } | Predicate::Between { operand, .. } =>
+                operand.aggregates(),
+            Predicate::And(left, right) | Predicate::Or(left, right) =>
+                left.aggregates().into_iter().chain(right.aggregates()).collect(),
+            Predicate::Not(negated) => negated.aggregates()
+        }
+    }
+}
+
+impl Predicate {
+    fn precedence(&self) -> u8 {
+        match self {
+            Predicate::Or(_, _) => 1,
+            Predicate::And(_, _) => 2,
+            _ => 3
+        }
+    }
+
+    fn to_operand_string(&self, precedence: u8) -> String {
+        if self.precedence() < precedence {
+            format!("({})", self)
+        } else {
+            self.to_string()
+        }
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let not = |negated: &bool| if *negated { "NOT " } else { "" };
+        match self {
+            Predicate::Filter(filter) =>
+                write!(f, "{} {} {}", filter.column_name, filter.filter_type, format_literal(&filter.value)),
+            Predicate::Comparison { left, filter_type, right } =>
+                write!(f, "{} {} {}", left, filter_type, right),
+            Predicate::IsNull { operand, negated } =>
+                write!(f, "{} IS {}NULL", operand, not(negated)),
+            Predicate::RegexMatch { left, pattern, negated } =>
+                write!(f, "{} {} {}", left, if *negated { "!~" } else { "~" }, pattern),
+            Predicate::Expression(expression) =>
+                write!(f, "{}", expression),
+            Predicate::In { operand, values, negated } =>
+                write!(f, "{} {}IN ({})", operand, not(negated), values.iter().map(format_literal).collect::<Vec<String>>().join(", ")),
+            Predicate::Between { operand, low, high, negated } =>
+                write!(f, "{} {}BETWEEN {} AND {}", operand, not(negated), format_literal(low), format_literal(high)),
+            Predicate::And(left, right) =>
+                write!(f, "{} AND {}", left.to_operand_string(self.precedence()), right.to_operand_string(self.precedence())),
+            Predicate::Or(left, right) =>
+                write!(f, "{} OR {}", left.to_operand_string(self.precedence()), right.to_operand_string(self.precedence())),
+            Predicate::Not(negated) =>
+                write!(f, "NOT {}", negated.to_operand_string(self.precedence()))
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Filter {
+    pub column_name: String,
+    pub value: Value,
+    pub filter_type: FilterType
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum FilterType {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+    Like,
+    ILike
+}
+
+impl FilterType {
+    fn from(input: &str) -> Result<FilterType, Error> {
+        match input {
+            ">" => Ok(FilterType::Greater),
+            ">=" => Ok(FilterType::GreaterOrEqual),
+            "<" => Ok(FilterType::Less),
+            "<=" => Ok(FilterType::LessOrEqual),
+            "=" => Ok(FilterType::Equal),
+            "!=" | "<>" => Ok(FilterType::NotEqual),
+            _ => Err(anyhow!(format!("Unknown filter type {}", input)))
+        }
+    }
+
+    // The comparison with the operands swapped
+    fn flipped(&self) -> FilterType {
+        match self {
+            FilterType::Greater => FilterType::Less,
+            FilterType::GreaterOrEqual => FilterType::LessOrEqual,
+            FilterType::Less => FilterType::Greater,
+            FilterType::LessOrEqual => FilterType::GreaterOrEqual,
+            filter_type => filter_type.clone()
+        }
+    }
+
+    // The pattern of LIKE and ILIKE cannot be swapped with the matched value
+    pub(crate) fn is_pattern_match(&self) -> bool {
+        matches!(self, FilterType::Like | FilterType::ILike)
+    }
+
+    pub(crate) fn matches(&self, left: &Value, right: &Value) -> bool {
+        match self {
+            FilterType::Greater => left > right,
+            FilterType::GreaterOrEqual => left >= right,
+            FilterType::Less => left < right,
+            FilterType::LessOrEqual => left <= right,
+            FilterType::Equal => left == right,
+            FilterType::NotEqual => left != right,
+            FilterType::Like => matches_like_pattern(&left.to_string(), &right.to_string()),
+            FilterType::ILike => matches_like_pattern(&left.to_string().to_lowercase(), &right.to_string().to_lowercase())
+        }
+    }
+}
+
+impl fmt::Display for FilterType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            FilterType::Greater => ">",
+            FilterType::GreaterOrEqual => ">=",
+            FilterType::Less => "<",
+            FilterType::LessOrEqual => "<=",
+            FilterType::Equal => "=",
+            FilterType::NotEqual => "!=",
+            FilterType::Like => "LIKE",
+            FilterType::ILike => "ILIKE"
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+// In a LIKE pattern "%" stands for any sequence of characters, "_" for any single character
+// and a backslash makes the following character match literally
+#[derive(Debug, PartialEq)]
+enum LikeSymbol {
+    Character(char),
+    AnyCharacter,
+    AnyCharacters
+}
+
+fn like_symbols(pattern: &str) -> Vec<LikeSymbol> {
+    let mut symbols: Vec<LikeSymbol> = Vec::new();
+    let mut characters = pattern.chars();
+    while let Some(character) = characters.next() {
+        symbols.push(match character {
+            '%' => LikeSymbol::AnyCharacters,
+            '_' => LikeSymbol::AnyCharacter,
+            '\\' => LikeSymbol::Character(characters.next().unwrap_or('\\')),
+            character => LikeSymbol::Character(character)
+        });
+    }
+    symbols
+}
+
+// The characters which all the values matching the pattern start with
+pub(crate) fn like_pattern_prefix(pattern: &str) -> String {
+    like_symbols(pattern).into_iter()
+        .map_while(|symbol| match symbol {
+            LikeSymbol::Character(character) => Some(character),
+            _ => None
+        })
+        .collect()
+}
+
+// Matches greedily, when a character does not match the matching resumes after the last "%",
+// which then consumes one more character of the value
+fn matches_like_pattern(value: &str, pattern: &str) -> bool {
+    let symbols = like_symbols(pattern);
+    let characters: Vec<char> = value.chars().collect();
+    let mut symbol_position = 0;
+    let mut character_position = 0;
+    let mut last_any_characters: Option<(usize, usize)> = None;
+    while character_position < characters.len() {
+        match symbols.get(symbol_position) {
+            Some(LikeSymbol::AnyCharacters) => {
+                last_any_characters = Some((symbol_position, character_position));
+                symbol_position += 1;
+            },
+            Some(LikeSymbol::AnyCharacter) => {
+                symbol_position += 1;
+                character_position += 1;
+            },
+            Some(LikeSymbol::Character(character)) if *character == characters[character_position] => {
+                symbol_position += 1;
+                character_position += 1;
+            },
+            _ => match last_any_characters {
+                Some((any_symbol_position, any_character_position)) => {
+                    symbol_position = any_symbol_position + 1;
+                    character_position = any_character_position + 1;
+                    last_any_characters = Some((any_symbol_position, character_position));
+                },
+                None => return false
+            }
+        }
+    }
+    symbols[symbol_position..].iter().all(|symbol| *symbol == LikeSymbol::AnyCharacters)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_error::Span;
+
+    #[test]
+    fn should_parse_correct_query_with_projection_and_greater_filter() {
+        let input = "PROJECT col1, col2 FILTER col3 > 'value'";
+        let query = Query::parse(input).unwrap();
+        assert_eq!(query, Query {
+            distinct: false,
+            projection: vec![ProjectionItem::Column("col1".to_string()), ProjectionItem::Column("col2".to_string())],
+            from: None,
+            joins: Vec::new(),
+            filter: Some(Predicate::Filter(Filter {
+                column_name: "col3".to_string(),
+                value: Value::Text("value".to_string()),
+                filter_type: FilterType::Greater
+            })),
+            group_by: Vec::new(),
+            having: None,
+            order_by: Vec::new(),
+            limit: None
+        })
+    }
+
+    #[test]
+    fn should_parse_correct_query_with_projection_using_a_single_column_and_greater_filter() {
+        let input = "PROJECT col1 FILTER col3 > 'value'";
+        let query = Query::parse(input).unwrap();
+        assert_eq!(query, Query {
+            distinct: false,
+            projection: vec![ProjectionItem::Column("col1".to_string())],
+            from: None,
+            joins: Vec::new(),
+            filter: Some(Predicate::Filter(Filter {
+                column_name: "col3".to_string(),
+                value: Value::Text("value".to_string()),
+                filter_type: FilterType::Greater
+            })),
+            group_by: Vec::new(),
+            having: None,
+            order_by: Vec::new(),
+            limit: None
+        })
+    }
+
+    #[test]
+    fn should_parse_query_which_uses_multiple_blanks_between_words() {
+        let input = "PROJECT   col1,   col2  FILTER     col3    >   'value'";
+        let query = Query::parse(input).unwrap();
+        assert_eq!(query, Query {
+            distinct: false,
+            projection: vec![ProjectionItem::Column("col1".to_string()), ProjectionItem::Column("col2".to_string())],
+            from: None,
+            joins: Vec::new(),
+            filter: Some(Predicate::Filter(Filter {
+                column_name: "col3".to_string(),
+                value: Value::Text("value".to_string()),
+                filter_type: FilterType::Greater
+            })),
+            group_by: Vec::new(),
+            having: None,
+            order_by: Vec::new(),
+            limit: None
+        })
+    }
+
+    #[test]
+    fn should_parse_query_with_an_equality_filter_and_number() {
+        let input = "PROJECT col1, col2 FILTER col3 = 42";
+        let query = Query::parse(input).unwrap();
+        assert_eq!(query, Query {
+            distinct: false,
+            projection: vec![ProjectionItem::Column("col1".to_string()), ProjectionItem::Column("col2".to_string())],
+            from: None,
+            joins: Vec::new(),
+            filter: Some(Predicate::Filter(Filter {
+                column_name: "col3".to_string(),
+                value: Value::Integer(42),
+                filter_type: FilterType::Equal
+            })),
+            group_by: Vec::new(),
+            having: None,
+            order_by: Vec::new(),
+            limit: None
+        })
+    }
+
+    #[test]
+    fn should_produce_error_when_projection_column_list_is_empty() {
+        let input = "PROJECT FILTER col3 > 'value'";
+        let query = Query::parse(input);
+        match query {
+            Err(e) => assert_eq!(e.to_string(), "expected column to project, found FILTER\nPROJECT FILTER col3 > 'value'\n        ^^^^^^"),
+            Ok(_) => panic!("Error expected"),
+        }
+    }
+
+    #[test]
+    fn should_produce_error_when_column_is_missing_from_the_filter() {
+        let input = "PROJECT col1, col2 FILTER > 'value'";
+        let query = Query::parse(input);
+        match query {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "expected column in the filter, found >\nPROJECT col1, col2 FILTER > 'value'\n                          ^"
+            ),
+            Ok(_) => panic!("Error expected"),
+        }
+    }
+
+    #[test]
+    fn should_parse_query_with_no_filter() {
+        let input = "PROJECT col1, col2";
+        let query = Query::parse(input).unwrap();
+        assert_eq!(query, Query {
+            distinct: false,
+            projection: vec![ProjectionItem::Column("col1".to_string()), ProjectionItem::Column("col2".to_string())],
+            from: None,
+            joins: Vec::new(),
+            filter: None,
+            group_by: Vec::new(),
+            having: None,
+            order_by: Vec::new(),
+            limit: None
+        })
+    }
+
+    #[test]
+    fn should_produce_error_when_typo_in_project_keyword() {
+        let input = "PROJECTION col1, col2 FILTER col3 > 'value'";
+        let query = Query::parse(input);
+        match query {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "expected keyword PROJECT or SELECT, found PROJECTION\nPROJECTION col1, col2 FILTER col3 > 'value'\n^^^^^^^^^^"
+            ),
+            Ok(_) => panic!("Error expected"),
+        }
+    }
+
+    #[test]
+    fn should_produce_error_when_typo_in_filter_keyword() {
+        let input = "PROJECT col1, col2 FILTRE col3 > 'value'";
+        let query = Query::parse(input);
+        match query {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "expected keyword FILTER, found FILTRE\nPROJECT col1, col2 FILTRE col3 > 'value'\n                   ^^^^^^"
+            ),
+            Ok(_) => panic!("Error expected"),
+        }
+    }
+
+    #[test]
+    fn should_produce_error_when_operator_is_missing_in_filter() {
+        let input = "PROJECT col1, col2 FILTER col3 'value'";
+        let query = Query::parse(input);
+        match query {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "expected comparison operator, found 'value'\nPROJECT col1, col2 FILTER col3 'value'\n                               ^^^^^^^"
+            ),
+            Ok(_) => panic!("Error expected"),
+        }
+    }
+
+    #[test]
+    fn should_produce_error_when_value_is_missing_in_filter() {
+        let input = "PROJECT col1, col2 FILTER col3 >";
+        let query = Query::parse(input);
+        match query {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "expected value to compare with, found end of query\nPROJECT col1, col2 FILTER col3 >\n                                ^"
+            ),
+            Ok(_) => panic!("Error expected"),
+        }
+    }
 
-impl FilterType {
-    fn from(input: &str) -> Result<FilterType, Error> {
-        match input {
-            ">" => Ok(FilterType::Greater),
-            "=" => Ok(FilterType::Equal),
-            _ => Err(anyhow!(format!("Unknown filter type {}", input)))
+    #[test]
+    fn should_produce_error_when_there_are_dangling_symbols_after_query_left() {
+        let input = "PROJECT col1, col2 FILTER col3 > 'value' abc";
+        let query = Query::parse(input);
+        match query {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "expected end of query, found abc\nPROJECT col1, col2 FILTER col3 > 'value' abc\n                                         ^^^"
+            ),
+            Ok(_) => panic!("Error expected"),
         }
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
+    fn condition(column_name: &str, filter_type: FilterType, value: Value) -> Predicate {
+        Predicate::Filter(Filter {
+            column_name: column_name.to_string(),
+            value,
+            filter_type
+        })
+    }
 
     #[test]
-    fn should_parse_correct_query_with_projection_and_greater_filter() {
-        let input = "PROJECT col1, col2 FILTER col3 > \"value\"";
+    fn should_parse_filter_where_and_binds_tighter_than_or() {
+        let input = "PROJECT col1 FILTER col1 = 1 OR col2 = 2 AND col3 = 3";
         let query = Query::parse(input).unwrap();
-        assert_eq!(query, Query {
-            column_names: vec!["col1".to_string(), "col2".to_string()],
-            filter: Some(Filter {
-                column_name: "col3".to_string(),
-                value: Value::Text("value".to_string()),
-                filter_type: FilterType::Greater
-            })
-        })
+        assert_eq!(query.filter, Some(Predicate::Or(
+            Box::new(condition("col1", FilterType::Equal, Value::Integer(1))),
+            Box::new(Predicate::And(
+                Box::new(condition("col2", FilterType::Equal, Value::Integer(2))),
+                Box::new(condition("col3", FilterType::Equal, Value::Integer(3)))
+            ))
+        )))
     }
 
     #[test]
-    fn should_parse_correct_query_with_projection_using_a_single_column_and_greater_filter() {
-        let input = "PROJECT col1 FILTER col3 > \"value\"";
+    fn should_parse_filter_with_parentheses_and_negation() {
+        let input = "PROJECT col1 FILTER NOT (col1 = 1 OR col2 > 'b') AND col3 = 3";
         let query = Query::parse(input).unwrap();
-        assert_eq!(query, Query {
-            column_names: vec!["col1".to_string()],
-            filter: Some(Filter {
-                column_name: "col3".to_string(),
-                value: Value::Text("value".to_string()),
-                filter_type: FilterType::Greater
-            })
-        })
+        assert_eq!(query.filter, Some(Predicate::And(
+            Box::new(Predicate::Not(Box::new(Predicate::Or(
+                Box::new(condition("col1", FilterType::Equal, Value::Integer(1))),
+                Box::new(condition("col2", FilterType::Greater, Value::Text("b".to_string())))
+            )))),
+            Box::new(condition("col3", FilterType::Equal, Value::Integer(3)))
+        )))
     }
 
     #[test]
-    fn should_parse_query_which_uses_multiple_blanks_between_words() {
-        let input = "PROJECT   col1,   col2  FILTER     col3    >   \"value\"";
-        let query = Query::parse(input).unwrap();
-        assert_eq!(query, Query {
-            column_names: vec!["col1".to_string(), "col2".to_string()],
-            filter: Some(Filter {
-                column_name: "col3".to_string(),
-                value: Value::Text("value".to_string()),
-                filter_type: FilterType::Greater
-            })
-        })
+    fn should_produce_error_when_closing_parenthesis_is_missing_in_filter() {
+        let input = "PROJECT col1 FILTER (col1 = 1 OR col2 = 2";
+        let query = Query::parse(input);
+        match query {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "expected closing parenthesis, found end of query\nPROJECT col1 FILTER (col1 = 1 OR col2 = 2\n                                         ^"
+            ),
+            Ok(_) => panic!("Error expected"),
+        }
     }
 
     #[test]
-    fn should_parse_query_with_an_equality_filter_and_number() {
-        let input = "PROJECT col1, col2 FILTER col3 = 42";
+    fn should_parse_all_comparison_operators() {
+        let operators = vec![
+            (">", FilterType::Greater),
+            (">=", FilterType::GreaterOrEqual),
+            ("<", FilterType::Less),
+            ("<=", FilterType::LessOrEqual),
+            ("=", FilterType::Equal),
+            ("!=", FilterType::NotEqual),
+            ("<>", FilterType::NotEqual)
+        ];
+        for (operator, filter_type) in operators.into_iter() {
+            let input = format!("PROJECT col1 FILTER col2 {} 5", operator);
+            let query = Query::parse(&input).unwrap();
+            assert_eq!(query.filter, Some(condition("col2", filter_type, Value::Integer(5))));
+        }
+    }
+
+    #[test]
+    fn should_parse_null_checks() {
+        let input = "PROJECT col1 FILTER col1 IS NULL OR col2 IS NOT NULL";
         let query = Query::parse(input).unwrap();
-        assert_eq!(query, Query {
-            column_names: vec!["col1".to_string(), "col2".to_string()],
-            filter: Some(Filter {
-                column_name: "col3".to_string(),
-                value: Value::Integer(42),
-                filter_type: FilterType::Equal
+        assert_eq!(query.filter, Some(Predicate::Or(
+            Box::new(Predicate::IsNull {
+                operand: Expression::Column("col1".to_string()),
+                negated: false
+            }),
+            Box::new(Predicate::IsNull {
+                operand: Expression::Column("col2".to_string()),
+                negated: true
             })
-        })
+        )))
     }
 
     #[test]
-    fn should_produce_error_when_projection_column_list_is_empty() {
-        let input = "PROJECT FILTER col3 > \"value\"";
+    fn should_produce_error_when_null_keyword_is_missing_in_null_check() {
+        let input = "PROJECT col1 FILTER col1 IS NOT 5";
         let query = Query::parse(input);
         match query {
-            Err(e) => assert_eq!(e.to_string(), "Projection column list is empty"),
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "expected keyword NULL, found 5\nPROJECT col1 FILTER col1 IS NOT 5\n                                ^"
+            ),
             Ok(_) => panic!("Error expected"),
         }
     }
 
     #[test]
-    fn should_produce_error_when_column_is_missing_from_the_filter() {
-        let input = "PROJECT col1, col2 FILTER > \"value\"";
+    fn should_parse_in_lists_and_ranges() {
+        let query = Query::parse("PROJECT col1 FILTER col1 IN ('a', 2) OR col2 NOT IN (3) AND col3 BETWEEN 1 AND 5 AND col4 NOT BETWEEN 'a' AND 'b'").unwrap();
+        assert_eq!(query.filter, Some(Predicate::Or(
+            Box::new(Predicate::In {
+                operand: Expression::Column("col1".to_string()),
+                values: vec![Value::Text("a".to_string()), Value::Integer(2)],
+                negated: false
+            }),
+            Box::new(Predicate::And(
+                Box::new(Predicate::And(
+                    Box::new(Predicate::In {
+                        operand: Expression::Column("col2".to_string()),
+                        values: vec![Value::Integer(3)],
+                        negated: true
+                    }),
+                    Box::new(Predicate::Between {
+                        operand: Expression::Column("col3".to_string()),
+                        low: Value::Integer(1),
+                        high: Value::Integer(5),
+                        negated: false
+                    })
+                )),
+                Box::new(Predicate::Between {
+                    operand: Expression::Column("col4".to_string()),
+                    low: Value::Text("a".to_string()),
+                    high: Value::Text("b".to_string()),
+                    negated: true
+                })
+            ))
+        )));
+    }
+
+    #[test]
+    fn should_produce_error_when_in_list_or_range_is_incomplete() {
+        let expected_errors = vec![
+            ("PROJECT a FILTER a IN 1, 2", "expected opening parenthesis, found 1\nPROJECT a FILTER a IN 1, 2\n                      ^"),
+            ("PROJECT a FILTER a BETWEEN 1 5", "expected keyword AND, found 5\nPROJECT a FILTER a BETWEEN 1 5\n                             ^"),
+            ("PROJECT a FILTER a + 1 IN (1)", "only a column or an aggregate can be used with IN\nPROJECT a FILTER a + 1 IN (1)\n                 ^^^^^"),
+            ("PROJECT a FILTER a NOT IS NULL", "expected keyword IN, BETWEEN, LIKE or ILIKE, found IS\nPROJECT a FILTER a NOT IS NULL\n                       ^^")
+        ];
+        for (input, expected_error) in expected_errors.into_iter() {
+            match Query::parse(input) {
+                Err(e) => assert_eq!(e.to_string(), expected_error),
+                Ok(_) => panic!("Error expected for {}", input)
+            }
+        }
+    }
+
+    #[test]
+    fn should_parse_case_expressions() {
+        let query = Query::parse("PROJECT CASE WHEN a > 1 AND (b IS NULL OR NOT c IN (1, 2)) THEN 'x' WHEN d ~ 'y' THEN e || 'z' END AS f, \
+            CASE g + 1 WHEN 2 THEN -h ELSE NULL END \
+            GROUP BY CASE g WHEN 1 THEN 1 END ORDER BY CASE WHEN i BETWEEN 1 AND 2 THEN 1 ELSE 0 END DESC").unwrap();
+        assert_eq!(query.projection.iter().map(|item| item.to_string()).collect::<Vec<String>>(), vec![
+            "CASE WHEN a > 1 AND (b IS NULL OR NOT c IN (1, 2)) THEN 'x' WHEN d ~ 'y' THEN e || 'z' END AS f",
+            "CASE g + 1 WHEN 2 THEN -h ELSE NULL END"
+        ]);
+        assert_eq!(query.group_by[0].to_string(), "CASE g WHEN 1 THEN 1 END");
+        assert_eq!(query.order_by[0].expression.to_string(), "CASE WHEN i BETWEEN 1 AND 2 THEN 1 ELSE 0 END");
+        assert_eq!(query.order_by[0].direction, SortDirection::Descending);
+        match &query.projection[0] {
+            ProjectionItem::Aliased { item, .. } => match item.as_ref() {
+                ProjectionItem::Expression(expression) => assert_eq!(expression.column_names(), vec!["a", "b", "c", "d", "e"]),
+                item => panic!("Unexpected item {:?}", item)
+            },
+            item => panic!("Unexpected item {:?}", item)
+        }
+        let expected_errors = vec![
+            ("PROJECT CASE WHEN a = 1 'x' END", "expected keyword THEN, found 'x'\nPROJECT CASE WHEN a = 1 'x' END\n                        ^^^"),
+            ("PROJECT CASE a ELSE 1 END", "expected keyword WHEN, found ELSE\nPROJECT CASE a ELSE 1 END\n               ^^^^"),
+            ("PROJECT CASE WHEN a = 1 THEN 1 ELSE 2 FILTER a = 1", "expected keyword END, found FILTER\nPROJECT CASE WHEN a = 1 THEN 1 ELSE 2 FILTER a = 1\n                                      ^^^^^^")
+        ];
+        for (input, expected_error) in expected_errors.into_iter() {
+            match Query::parse(input) {
+                Err(e) => assert_eq!(e.to_string(), expected_error),
+                Ok(_) => panic!("Error expected for {}", input)
+            }
+        }
+    }
+
+    #[test]
+    fn should_parse_pattern_matches() {
+        let query = Query::parse("PROJECT col1 FILTER col1 LIKE 'a%' AND col2 NOT ILIKE '_b' AND 'c' LIKE col3").unwrap();
+        assert_eq!(query.filter.unwrap().conjuncts(), vec![
+            &condition("col1", FilterType::Like, Value::Text("a%".to_string())),
+            &Predicate::Not(Box::new(condition("col2", FilterType::ILike, Value::Text("_b".to_string())))),
+            &Predicate::Comparison {
+                left: Expression::Literal(Value::Text("c".to_string())),
+                filter_type: FilterType::Like,
+                right: Expression::Column("col3".to_string())
+            }
+        ]);
+    }
+
+    #[test]
+    fn should_parse_regex_matches_with_patterns_compiled_once() {
+        let query = Query::parse("PROJECT col1 FILTER col1 ~ '^San ' OR LOWER(col2) !~ 'x[0-9]+'").unwrap();
+        match query.filter {
+            Some(Predicate::Or(left, right)) => match (*left, *right) {
+                (Predicate::RegexMatch { left: matched_left, pattern: left_pattern, negated: false },
+                 Predicate::RegexMatch { left: matched_right, pattern: right_pattern, negated: true }) => {
+                    assert_eq!(matched_left, Expression::Column("col1".to_string()));
+                    assert_eq!(matched_right.column_names(), vec!["col2"]);
+                    assert!(left_pattern.is_match("San Diego") && !left_pattern.is_match("Santa San Diego"));
+                    assert!(right_pattern.is_match("ax12b") && !right_pattern.is_match("ax"));
+                },
+                predicates => panic!("Unexpected predicates {:?}", predicates)
+            },
+            filter => panic!("Unexpected filter {:?}", filter)
+        }
+        let expected_errors = vec![
+            ("PROJECT a FILTER a ~ '[a-'", "invalid regular expression '[a-'\nPROJECT a FILTER a ~ '[a-'\n                     ^^^^^"),
+            ("PROJECT a FILTER a !~ b", "expected string with a regular expression, found b\nPROJECT a FILTER a !~ b\n                      ^")
+        ];
+        for (input, expected_error) in expected_errors.into_iter() {
+            match Query::parse(input) {
+                Err(e) => assert_eq!(e.to_string(), expected_error),
+                Ok(_) => panic!("Error expected for {}", input)
+            }
+        }
+    }
+
+    #[test]
+    fn should_match_values_against_like_patterns() {
+        let expected_matches = vec![
+            ("Berlin", "Ber%", true),
+            ("Berlin", "%lin", true),
+            ("Berlin", "B_r_i_", true),
+            ("Berlin", "%e%i%", true),
+            ("Berlin", "Ber", false),
+            ("Berlin", "_erli", false),
+            ("Berlin", "%", true),
+            ("", "%", true),
+            ("", "_", false),
+            ("aab", "%ab", true),
+            ("100%", "100\\%", true),
+            ("1000", "100\\%", false),
+            ("a_b", "a\\_b", true)
+        ];
+        for (value, pattern, expected_match) in expected_matches.into_iter() {
+            assert_eq!(matches_like_pattern(value, pattern), expected_match, "{} LIKE {}", value, pattern);
+        }
+        assert_eq!(like_pattern_prefix("Ber%n"), "Ber");
+        assert_eq!(like_pattern_prefix("a\\_b_"), "a_b");
+    }
+
+    fn order_by(column_name: &str, direction: SortDirection, nulls: NullsOrder) -> OrderBy {
+        OrderBy {
+            expression: Expression::Column(column_name.to_string()),
+            direction,
+            nulls
+        }
+    }
+
+    #[test]
+    fn should_parse_query_with_filter_and_order_by() {
+        let input = "PROJECT col1, col2 FILTER col3 = 42 ORDER BY col1 DESC, col2";
+        let query = Query::parse(input).unwrap();
+        assert_eq!(query.filter, Some(condition("col3", FilterType::Equal, Value::Integer(42))));
+        assert_eq!(query.order_by, vec![
+            order_by("col1", SortDirection::Descending, NullsOrder::Last),
+            order_by("col2", SortDirection::Ascending, NullsOrder::First)
+        ])
+    }
+
+    #[test]
+    fn should_parse_query_with_order_by_using_explicit_position_of_nulls() {
+        let input = "PROJECT col1 ORDER BY col1 ASC NULLS LAST, col2 DESC NULLS FIRST , col3";
+        let query = Query::parse(input).unwrap();
+        assert_eq!(query.filter, None);
+        assert_eq!(query.order_by, vec![
+            order_by("col1", SortDirection::Ascending, NullsOrder::Last),
+            order_by("col2", SortDirection::Descending, NullsOrder::First),
+            order_by("col3", SortDirection::Ascending, NullsOrder::First)
+        ])
+    }
+
+    #[test]
+    fn should_produce_error_when_by_keyword_is_missing_in_order_by() {
+        let input = "PROJECT col1 ORDER col1";
         let query = Query::parse(input);
         match query {
             Err(e) => assert_eq!(
                 e.to_string(),
-                "Unknown filter operator in [\"PROJECT\", \"col1,\", \"col2\", \"FILTER\", \">\", \"\\\"value\\\"\"] at position 5"
+                "expected keyword BY, found col1\nPROJECT col1 ORDER col1\n                   ^^^^"
             ),
             Ok(_) => panic!("Error expected"),
         }
     }
 
     #[test]
-    fn should_parse_query_with_no_filter() {
-        let input = "PROJECT col1, col2";
+    fn should_parse_query_with_limit_and_offset() {
+        let input = "PROJECT col1 FILTER col2 > 1 ORDER BY col1 LIMIT 10 OFFSET 20";
         let query = Query::parse(input).unwrap();
-        assert_eq!(query, Query {
-            column_names: vec!["col1".to_string(), "col2".to_string()],
-            filter: None
-        })
+        assert_eq!(query.order_by, vec![order_by("col1", SortDirection::Ascending, NullsOrder::First)]);
+        assert_eq!(query.limit, Some(Limit { count: 10, offset: 20 }))
     }
 
     #[test]
-    fn should_produce_error_when_typo_in_project_keyword() {
-        let input = "PROJECTION col1, col2 FILTER col3 > \"value\"";
+    fn should_parse_query_with_limit_without_offset() {
+        let input = "PROJECT col1 LIMIT 5";
+        let query = Query::parse(input).unwrap();
+        assert_eq!(query.limit, Some(Limit { count: 5, offset: 0 }))
+    }
+
+    #[test]
+    fn should_produce_error_when_limit_is_not_a_number() {
+        let input = "PROJECT col1 LIMIT -5";
         let query = Query::parse(input);
         match query {
             Err(e) => assert_eq!(
                 e.to_string(),
-                "Expected to find keyword PROJECT in [\"PROJECTION\", \"col1,\", \"col2\", \"FILTER\", \"col3\", \">\", \"\\\"value\\\"\"] at position 0"
+                "expected a non-negative number of rows, found -\nPROJECT col1 LIMIT -5\n                   ^"
             ),
             Ok(_) => panic!("Error expected"),
         }
     }
 
     #[test]
-    fn should_produce_error_when_typo_in_filter_keyword() {
-        let input = "PROJECT col1, col2 FILTRE col3 > \"value\"";
+    fn should_parse_query_with_aggregates_and_group_by() {
+        let input = "PROJECT country, COUNT(*), SUM(population), AVG( area ) FILTER area > 100 GROUP BY country ORDER BY COUNT(*) DESC";
+        let query = Query::parse(input).unwrap();
+        assert_eq!(query.projection, vec![
+            ProjectionItem::Column("country".to_string()),
+            ProjectionItem::Aggregate(Aggregate { function: AggregateFunction::Count, column_name: None, distinct: false }),
+            ProjectionItem::Aggregate(Aggregate { function: AggregateFunction::Sum, column_name: Some("population".to_string()), distinct: false }),
+            ProjectionItem::Aggregate(Aggregate { function: AggregateFunction::Avg, column_name: Some("area".to_string()), distinct: false })
+        ]);
+        assert_eq!(query.filter, Some(condition("area", FilterType::Greater, Value::Integer(100))));
+        assert_eq!(query.group_by, vec![Expression::Column("country".to_string())]);
+        assert_eq!(query.order_by, vec![OrderBy {
+            expression: Expression::Aggregate(Aggregate { function: AggregateFunction::Count, column_name: None, distinct: false }),
+            direction: SortDirection::Descending,
+            nulls: NullsOrder::Last
+        }]);
+        assert!(query.is_aggregation());
+    }
+
+    #[test]
+    fn should_parse_query_with_global_aggregates_and_multiple_group_by_columns() {
+        let query = Query::parse("PROJECT MIN(col1), MAX(col1)").unwrap();
+        assert_eq!(query.projection, vec![
+            ProjectionItem::Aggregate(Aggregate { function: AggregateFunction::Min, column_name: Some("col1".to_string()), distinct: false }),
+            ProjectionItem::Aggregate(Aggregate { function: AggregateFunction::Max, column_name: Some("col1".to_string()), distinct: false })
+        ]);
+        assert!(query.is_aggregation());
+        let query = Query::parse("PROJECT col1, col2 GROUP BY col1, col2 LIMIT 1").unwrap();
+        assert_eq!(query.group_by, vec![Expression::Column("col1".to_string()), Expression::Column("col2".to_string())]);
+        assert!(query.is_aggregation());
+    }
+
+    #[test]
+    fn should_parse_distinct_projection_and_aggregates() {
+        let query = Query::parse("SELECT DISTINCT country, COUNT(DISTINCT cities.city) FROM cities GROUP BY country HAVING COUNT(DISTINCT cities.city) > 1").unwrap();
+        assert!(query.distinct);
+        assert_eq!(query.projection[1], ProjectionItem::Aggregate(Aggregate {
+            function: AggregateFunction::Count,
+            column_name: Some("cities.city".to_string()),
+            distinct: true
+        }));
+        assert_eq!(query.projection[1].to_string(), "COUNT(DISTINCT cities.city)");
+        let renamed = query.map_column_names(&|column_name| Ok(column_name.trim_start_matches("cities.").to_string())).unwrap();
+        assert_eq!(renamed.having.unwrap().aggregates().into_iter().map(|aggregate| aggregate.to_string()).collect::<Vec<String>>(), vec!["COUNT(DISTINCT city)"]);
+        assert!(!Query::parse("PROJECT country").unwrap().distinct);
+        match Query::parse("PROJECT COUNT(DISTINCT *)") {
+            Err(e) => assert_eq!(e.to_string(), "only COUNT can be applied to *\nPROJECT COUNT(DISTINCT *)\n              ^^^^^^^^^^"),
+            Ok(_) => panic!("Error expected")
+        }
+        match Query::parse("PROJECT DISTINCT FILTER a = 1") {
+            Err(e) => assert_eq!(e.to_string(), "expected column to project, found FILTER\nPROJECT DISTINCT FILTER a = 1\n                 ^^^^^^"),
+            Ok(_) => panic!("Error expected")
+        }
+    }
+
+    #[test]
+    fn should_produce_error_when_star_is_used_with_aggregate_other_than_count() {
+        let input = "PROJECT SUM(*)";
         let query = Query::parse(input);
         match query {
             Err(e) => assert_eq!(
                 e.to_string(),
-                "Expected to find keyword FILTER in [\"PROJECT\", \"col1,\", \"col2\", \"FILTRE\", \"col3\", \">\", \"\\\"value\\\"\"] at position 3"
+                "only COUNT can be applied to *\nPROJECT SUM(*)\n            ^"
             ),
             Ok(_) => panic!("Error expected"),
         }
     }
 
     #[test]
-    fn should_produce_error_when_operator_is_missing_in_filter() {
-        let input = "PROJECT col1, col2 FILTER col3 \"value\"";
+    fn should_parse_query_with_having() {
+        let input = "PROJECT country, COUNT(*) GROUP BY country HAVING COUNT(*) > 2 AND country != 'Spain' ORDER BY country";
+        let query = Query::parse(input).unwrap();
+        assert_eq!(query.having, Some(Predicate::And(
+            Box::new(Predicate::Comparison {
+                left: Expression::Aggregate(Aggregate { function: AggregateFunction::Count, column_name: None, distinct: false }),
+                filter_type: FilterType::Greater,
+                right: Expression::Literal(Value::Integer(2))
+            }),
+            Box::new(condition("country", FilterType::NotEqual, Value::Text("Spain".to_string())))
+        )));
+        assert_eq!(query.order_by, vec![order_by("country", SortDirection::Ascending, NullsOrder::First)]);
+    }
+
+    #[test]
+    fn should_produce_error_when_aggregate_in_having_is_not_projected() {
+        let input = "PROJECT country, COUNT(*) GROUP BY country HAVING SUM(population) > 2";
+        let query = Query::parse(input);
+        match query {
+            Err(e) => assert_eq!(e.to_string(), "aggregate SUM(population) used in HAVING must appear in the projection\nPROJECT country, COUNT(*) GROUP BY country HAVING SUM(population) > 2\n                                                  ^^^^^^^^^^^^^^^^^^^"),
+            Ok(_) => panic!("Error expected"),
+        }
+    }
+
+    #[test]
+    fn should_produce_error_when_column_in_having_is_not_grouped() {
+        let input = "PROJECT country, COUNT(*) GROUP BY country HAVING city IS NULL";
+        let query = Query::parse(input);
+        match query {
+            Err(e) => assert_eq!(e.to_string(), "column city used in HAVING must appear in GROUP BY\nPROJECT country, COUNT(*) GROUP BY country HAVING city IS NULL\n                                                  ^^^^^^^^^^^^"),
+            Ok(_) => panic!("Error expected"),
+        }
+    }
+
+    #[test]
+    fn should_produce_error_when_aggregate_is_used_in_filter() {
+        let input = "PROJECT country FILTER COUNT(*) > 2";
+        let query = Query::parse(input);
+        match query {
+            Err(e) => assert_eq!(e.to_string(), "aggregate COUNT(*) cannot be used in FILTER, aggregated rows can be filtered with HAVING\nPROJECT country FILTER COUNT(*) > 2\n                       ^^^^^^^^^^^^"),
+            Ok(_) => panic!("Error expected"),
+        }
+    }
+
+    #[test]
+    fn should_parse_query_with_from() {
+        let input = "PROJECT col1, col2 FROM table1 FILTER col3 = 42";
+        let query = Query::parse(input).unwrap();
+        assert_eq!(query, Query {
+            distinct: false,
+            projection: vec![ProjectionItem::Column("col1".to_string()), ProjectionItem::Column("col2".to_string())],
+            from: Some("table1".to_string()),
+            joins: Vec::new(),
+            filter: Some(condition("col3", FilterType::Equal, Value::Integer(42))),
+            group_by: Vec::new(),
+            having: None,
+            order_by: Vec::new(),
+            limit: None
+        })
+    }
+
+    #[test]
+    fn should_produce_error_when_table_name_is_missing_in_from() {
+        let input = "PROJECT col1 FROM FILTER col3 = 42";
         let query = Query::parse(input);
         match query {
             Err(e) => assert_eq!(
                 e.to_string(),
-                "Unknown filter operator in [\"PROJECT\", \"col1,\", \"col2\", \"FILTER\", \"col3\", \"\\\"value\\\"\"] at position 5"
+                "expected table name, found FILTER\nPROJECT col1 FROM FILTER col3 = 42\n                  ^^^^^^"
             ),
             Ok(_) => panic!("Error expected"),
         }
     }
 
     #[test]
-    fn should_produce_error_when_value_is_missing_in_filter() {
-        let input = "PROJECT col1, col2 FILTER col3 >";
+    fn should_parse_query_with_joins() {
+        let input = "PROJECT cities.city, countries.capital FROM cities JOIN countries ON cities.country = countries.name LEFT OUTER JOIN languages ON language = languages.name FILTER cities.population > 1000000";
+        let query = Query::parse(input).unwrap();
+        assert_eq!(query.from, Some("cities".to_string()));
+        assert_eq!(query.joins, vec![
+            Join {
+                join_type: JoinType::Inner,
+                table_name: "countries".to_string(),
+                left_column_name: "cities.country".to_string(),
+                right_column_name: "countries.name".to_string()
+            },
+            Join {
+                join_type: JoinType::LeftOuter,
+                table_name: "languages".to_string(),
+                left_column_name: "language".to_string(),
+                right_column_name: "languages.name".to_string()
+            }
+        ]);
+        assert_eq!(query.filter, Some(condition("cities.population", FilterType::Greater, Value::Integer(1000000))));
+        let query = Query::parse("PROJECT a FROM t1 INNER JOIN t2 ON x = y LEFT JOIN t3 ON x = z").unwrap();
+        assert_eq!(query.joins.iter().map(|join| join.join_type).collect::<Vec<JoinType>>(), vec![JoinType::Inner, JoinType::LeftOuter]);
+    }
+
+    #[test]
+    fn should_produce_error_when_join_condition_is_missing() {
+        let input = "PROJECT a FROM t1 JOIN t2 FILTER x = 1";
         let query = Query::parse(input);
         match query {
             Err(e) => assert_eq!(
                 e.to_string(),
-                "Could not find value to filter by in the filter in [\"PROJECT\", \"col1,\", \"col2\", \"FILTER\", \"col3\", \">\"] at position 6"
+                "expected keyword ON, found FILTER\nPROJECT a FROM t1 JOIN t2 FILTER x = 1\n                          ^^^^^^"
             ),
             Ok(_) => panic!("Error expected"),
         }
     }
 
     #[test]
-    fn should_produce_error_when_there_are_dangling_symbols_after_query_left() {
-        let input = "PROJECT col1, col2 FILTER col3 > \"value\". abc";
+    fn should_parse_strings_with_spaces_and_escaped_quotes() {
+        let input = "PROJECT city_name ,country FILTER city_name = 'New York' OR nickname = 'The \\'Big Apple\\''";
+        let query = Query::parse(input).unwrap();
+        assert_eq!(query.projection, vec![ProjectionItem::Column("city_name".to_string()), ProjectionItem::Column("country".to_string())]);
+        assert_eq!(query.filter, Some(Predicate::Or(
+            Box::new(condition("city_name", FilterType::Equal, Value::Text("New York".to_string()))),
+            Box::new(condition("nickname", FilterType::Equal, Value::Text("The 'Big Apple'".to_string())))
+        )))
+    }
+
+    #[test]
+    fn should_parse_quoted_numbers_as_strings() {
+        let query = Query::parse("PROJECT col1 FILTER col1 = '42' AND col2>-1.5").unwrap();
+        assert_eq!(query.filter, Some(Predicate::And(
+            Box::new(condition("col1", FilterType::Equal, Value::Text("42".to_string()))),
+            Box::new(condition("col2", FilterType::Greater, Value::Float(-1.5)))
+        )))
+    }
+
+    #[test]
+    fn should_produce_error_when_value_is_a_keyword() {
+        let input = "PROJECT col1 FILTER col1 = ORDER BY col1";
         let query = Query::parse(input);
         match query {
             Err(e) => assert_eq!(
                 e.to_string(),
-                "Unexpected suffix found in [\"PROJECT\", \"col1,\", \"col2\", \"FILTER\", \"col3\", \">\", \"\\\"value\\\".\", \"abc\"] at position 7"
+                "expected value to compare with, found ORDER\nPROJECT col1 FILTER col1 = ORDER BY col1\n                           ^^^^^"
             ),
             Ok(_) => panic!("Error expected"),
         }
     }
+
+    #[test]
+    fn should_parse_quoted_identifiers_distinct_from_strings() {
+        let input = "PROJECT \"Area (km2)\", `FILTER`, MAX(\"Area (km2)\") FILTER \"city\" = 'city' GROUP BY \"Area (km2)\", `FILTER` ORDER BY MAX(\"Area (km2)\")";
+        let query = Query::parse(input).unwrap();
+        assert_eq!(query.projection, vec![
+            ProjectionItem::Column("Area (km2)".to_string()),
+            ProjectionItem::Column("FILTER".to_string()),
+            ProjectionItem::Aggregate(Aggregate { function: AggregateFunction::Max, column_name: Some("Area (km2)".to_string()), distinct: false })
+        ]);
+        assert_eq!(query.filter, Some(condition("city", FilterType::Equal, Value::Text("city".to_string()))));
+        assert_eq!(query.group_by, vec![Expression::Column("Area (km2)".to_string()), Expression::Column("FILTER".to_string())]);
+        assert_eq!(query.order_by, vec![OrderBy {
+            expression: Expression::Aggregate(Aggregate { function: AggregateFunction::Max, column_name: Some("Area (km2)".to_string()), distinct: false }),
+            direction: SortDirection::Ascending,
+            nulls: NullsOrder::First
+        }]);
+        let mapped_query = query.map_column_names(&|column_name| Ok(format!("t.{}", column_name))).unwrap();
+        assert_eq!(mapped_query.order_by[0].expression.to_string(), "MAX(t.Area (km2))");
+        assert_eq!(mapped_query.projection[0], ProjectionItem::Column("t.Area (km2)".to_string()));
+    }
+
+    #[test]
+    fn should_parse_sql_query_into_the_same_query() {
+        let sql_query = Query::parse("SELECT city, COUNT(*) FROM cities LEFT JOIN countries ON country = name WHERE population > 1000 GROUP BY city HAVING COUNT(*) > 1 ORDER BY city LIMIT 5").unwrap();
+        let query = Query::parse("PROJECT city, COUNT(*) FROM cities LEFT JOIN countries ON country = name FILTER population > 1000 GROUP BY city HAVING COUNT(*) > 1 ORDER BY city LIMIT 5").unwrap();
+        assert_eq!(sql_query, query);
+        assert_eq!(Query::parse_with_dialect("SELECT city WHERE population > 1000", Dialect::Sql).unwrap(), Query::parse("PROJECT city FILTER population > 1000").unwrap());
+    }
+
+    #[test]
+    fn should_produce_error_when_keyword_of_other_dialect_is_used() {
+        let expected_errors = vec![
+            ("SELECT city FILTER population > 1000", Dialect::Auto, "expected keyword WHERE, found FILTER\nSELECT city FILTER population > 1000\n            ^^^^^^"),
+            ("PROJECT city WHERE population > 1000", Dialect::Auto, "expected keyword FILTER, found WHERE\nPROJECT city WHERE population > 1000\n             ^^^^^"),
+            ("PROJECT city", Dialect::Sql, "expected keyword SELECT, found PROJECT\nPROJECT city\n^^^^^^^"),
+            ("city", Dialect::Auto, "expected keyword PROJECT or SELECT, found city\ncity\n^^^^")
+        ];
+        for (input, dialect, expected_error) in expected_errors.into_iter() {
+            match Query::parse_with_dialect(input, dialect) {
+                Err(e) => assert_eq!(e.to_string(), expected_error),
+                Ok(_) => panic!("Error expected"),
+            }
+        }
+    }
+
+    #[test]
+    fn should_report_span_of_offending_token_in_parse_error() {
+        let input = "PROJECT col1 FILTER col1 >= 'x' LIMT 5";
+        match Query::parse(input) {
+            Err(e) => {
+                assert_eq!(e.message, "expected end of query, found LIMT");
+                assert_eq!(e.span, Span { start: 32, end: 36 });
+                assert_eq!(&e.input[e.span.start..e.span.end], "LIMT");
+            },
+            Ok(_) => panic!("Error expected"),
+        }
+    }
+
+    #[test]
+    fn should_map_column_names_in_all_clauses() {
+        let query = Query::parse("PROJECT t.a, COUNT(*), SUM(t.b) FROM t FILTER t.c = 1 GROUP BY t.a HAVING SUM(t.b) > 2 ORDER BY SUM(t.b), t.a").unwrap();
+        let mapped_query = query.map_column_names(&|column_name| Ok(column_name.trim_start_matches("t.").to_string())).unwrap();
+        assert_eq!(mapped_query, Query::parse("PROJECT a, COUNT(*), SUM(b) FROM t FILTER c = 1 GROUP BY a HAVING SUM(b) > 2 ORDER BY SUM(b), a").unwrap());
+    }
+
+    #[test]
+    fn should_parse_all_columns_and_columns_matching_pattern() {
+        let query = Query::parse("PROJECT *, COLUMNS('population_.*') FILTER a = 1").unwrap();
+        assert_eq!(query.projection[0], ProjectionItem::AllColumns { except: Vec::new() });
+        assert_eq!(query.projection.iter().map(|item| item.to_string()).collect::<Vec<String>>(), vec!["*", "COLUMNS('population_.*')"]);
+        match &query.projection[1] {
+            ProjectionItem::MatchingColumns(pattern) => {
+                assert!(pattern.is_match("population_2020"));
+                assert!(!pattern.is_match("total_population_2020"));
+            },
+            item => panic!("Unexpected projection item {}", item)
+        }
+        let query = Query::parse("PROJECT * EXCEPT col1, \"col 2\" ORDER BY col3").unwrap();
+        assert_eq!(query.projection, vec![ProjectionItem::AllColumns { except: vec!["col1".to_string(), "col 2".to_string()] }]);
+    }
+
+    #[test]
+    fn should_produce_error_when_pattern_of_columns_is_invalid() {
+        let expected_errors = vec![
+            ("PROJECT COLUMNS('a(') FILTER a = 1", "invalid regular expression 'a('\nPROJECT COLUMNS('a(') FILTER a = 1\n                ^^^^"),
+            ("PROJECT COLUMNS(col1)", "expected string with a regular expression, found col1\nPROJECT COLUMNS(col1)\n                ^^^^"),
+            ("PROJECT * EXCEPT FILTER a = 1", "expected column to exclude, found FILTER\nPROJECT * EXCEPT FILTER a = 1\n                 ^^^^^^")
+        ];
+        for (input, expected_error) in expected_errors.into_iter() {
+            match Query::parse(input) {
+                Err(e) => assert_eq!(e.to_string(), expected_error),
+                Ok(_) => panic!("Error expected"),
+            }
+        }
+    }
+
+    #[test]
+    fn should_parse_expressions_respecting_precedence_of_operators() {
+        let query = Query::parse("PROJECT population / area AS density, city || ', ' || country, -(a + b) * 2, a - (b - c), (a - b) - c, 'x' AS label, NULL").unwrap();
+        assert_eq!(query.projection[0], ProjectionItem::Aliased {
+            item: Box::new(ProjectionItem::Expression(Expression::Binary {
+                operator: BinaryOperator::Divide,
+                left: Box::new(Expression::Column("population".to_string())),
+                right: Box::new(Expression::Column("area".to_string()))
+            })),
+            alias: "density".to_string()
+        });
+        assert_eq!(query.projection.iter().map(|item| item.to_string()).collect::<Vec<String>>(), vec![
+            "population / area AS density",
+            "city || ', ' || country",
+            "-(a + b) * 2",
+            "a - (b - c)",
+            "a - b - c",
+            "'x' AS label",
+            "NULL"
+        ]);
+        assert_eq!(query.projection.iter().map(|item| item.name()).collect::<Vec<String>>()[..2], ["density", "city || ', ' || country"]);
+        let mapped_query = query.map_column_names(&|column_name| Ok(format!("t.{}", column_name))).unwrap();
+        assert_eq!(mapped_query.projection[0].to_string(), "t.population / t.area AS density");
+    }
+
+    #[test]
+    fn should_produce_error_when_operand_is_missing_in_expression() {
+        let expected_errors = vec![
+            ("PROJECT a + FROM t", "expected operand, found FROM\nPROJECT a + FROM t\n            ^^^^"),
+            ("PROJECT (a * 2 FROM t", "expected closing parenthesis, found FROM\nPROJECT (a * 2 FROM t\n               ^^^^"),
+            ("PROJECT a AS FROM t", "expected alias, found FROM\nPROJECT a AS FROM t\n             ^^^^")
+        ];
+        for (input, expected_error) in expected_errors.into_iter() {
+            match Query::parse(input) {
+                Err(e) => assert_eq!(e.to_string(), expected_error),
+                Ok(_) => panic!("Error expected"),
+            }
+        }
+    }
+
+    #[test]
+    fn should_parse_comparisons_of_expressions_and_keep_column_compared_with_literal_as_filter() {
+        let query = Query::parse("PROJECT a FILTER population > area * 1000 AND LOWER(country) = 'spain' AND 5 <= b AND c = NULL").unwrap();
+        assert_eq!(query.filter.unwrap().conjuncts(), vec![
+            &Predicate::Comparison {
+                left: Expression::Column("population".to_string()),
+                filter_type: FilterType::Greater,
+                right: Expression::Binary {
+                    operator: BinaryOperator::Multiply,
+                    left: Box::new(Expression::Column("area".to_string())),
+                    right: Box::new(Expression::Literal(Value::Integer(1000)))
+                }
+            },
+            &Predicate::Comparison {
+                left: Expression::Function { function: Function::Lower, arguments: vec![Expression::Column("country".to_string())] },
+                filter_type: FilterType::Equal,
+                right: Expression::Literal(Value::Text("spain".to_string()))
+            },
+            &condition("b", FilterType::GreaterOrEqual, Value::Integer(5)),
+            &Predicate::Comparison {
+                left: Expression::Column("c".to_string()),
+                filter_type: FilterType::Equal,
+                right: Expression::Literal(Value::Null)
+            }
+        ]);
+    }
+
+    #[test]
+    fn should_parse_parenthesized_expressions_and_predicates_in_filter() {
+        let query = Query::parse("PROJECT SUM(x), COUNT(*) FILTER (a + b) * 2 > 1 OR (c = 1) HAVING (SUM(x) / COUNT(*)) > 2").unwrap();
+        assert_eq!(query.filter, Some(Predicate::Or(
+            Box::new(Predicate::Comparison {
+                left: Expression::Binary {
+                    operator: BinaryOperator::Multiply,
+                    left: Box::new(Expression::Binary {
+                        operator: BinaryOperator::Add,
+                        left: Box::new(Expression::Column("a".to_string())),
+                        right: Box::new(Expression::Column("b".to_string()))
+                    }),
+                    right: Box::new(Expression::Literal(Value::Integer(2)))
+                },
+                filter_type: FilterType::Greater,
+                right: Expression::Literal(Value::Integer(1))
+            }),
+            Box::new(condition("c", FilterType::Equal, Value::Integer(1)))
+        )));
+        let having = query.having.unwrap();
+        assert!(having.column_names().is_empty());
+        assert_eq!(having.aggregates().into_iter().map(|aggregate| aggregate.to_string()).collect::<Vec<String>>(), vec!["SUM(x)", "COUNT(*)"]);
+        match Query::parse("PROJECT a FILTER (a + b > 1") {
+            Err(e) => assert_eq!(e.to_string(), "expected closing parenthesis, found end of query\nPROJECT a FILTER (a + b > 1\n                           ^"),
+            Ok(_) => panic!("Error expected")
+        }
+        match Query::parse("PROJECT a FILTER LOWER(a, b) = 'x'") {
+            Err(e) => assert_eq!(e.to_string(), "function LOWER expects 1 argument(s), found 2\nPROJECT a FILTER LOWER(a, b) = 'x'\n                 ^^^^^^^^^^^"),
+            Ok(_) => panic!("Error expected")
+        }
+    }
+
+    #[test]
+    fn should_parse_date_literals_and_date_functions() {
+        let query = Query::parse("PROJECT EXTRACT(year FROM created), DATE_TRUNC('month', created + INTERVAL '1 day 2 hours') \
+            FILTER created BETWEEN DATE '2024-01-01' AND TIMESTAMP '2024-06-30 12:00' AND updated IN (DATE '2024-02-29', DATE '2024-03-01')").unwrap();
+        assert_eq!(query.projection[0], ProjectionItem::Expression(Expression::Function {
+            function: Function::Extract,
+            arguments: vec![Expression::Literal(Value::Text("YEAR".to_string())), Expression::Column("created".to_string())]
+        }));
+        assert_eq!(query.projection[1].name(), "DATE_TRUNC('month', created + INTERVAL '1 day 02:00:00')");
+        assert_eq!(query.projection[0].name(), "EXTRACT(YEAR FROM created)");
+        let filter = query.filter.unwrap();
+        assert_eq!(filter.to_string(),
+            "created BETWEEN DATE '2024-01-01' AND TIMESTAMP '2024-06-30 12:00:00' AND updated IN (DATE '2024-02-29', DATE '2024-03-01')");
+        assert_eq!(Query::parse(&format!("PROJECT a FILTER {}", filter)).unwrap().filter, Some(filter));
+        let expected_errors = vec![
+            ("PROJECT a FILTER a > DATE '2024-02-30'", "invalid date '2024-02-30'\nPROJECT a FILTER a > DATE '2024-02-30'\n                     ^^^^^^^^^^^^^^^^^"),
+            ("PROJECT a + INTERVAL '3 fortnights'", "invalid interval '3 fortnights'\nPROJECT a + INTERVAL '3 fortnights'\n            ^^^^^^^^^^^^^^^^^^^^^^^"),
+            ("PROJECT a FILTER a IN (DATE 5)", "expected string after DATE, found 5\nPROJECT a FILTER a IN (DATE 5)\n                            ^"),
+            ("PROJECT EXTRACT(CENTURY FROM a)", "expected field to extract, one of YEAR, QUARTER, MONTH, WEEK, DAY, DOW, DOY, HOUR, MINUTE, SECOND, EPOCH, found CENTURY\n\
+PROJECT EXTRACT(CENTURY FROM a)\n                ^^^^^^^"),
+            ("PROJECT EXTRACT(YEAR, a)", "expected keyword FROM, found ,\nPROJECT EXTRACT(YEAR, a)\n                    ^")
+        ];
+        for (query, expected_error) in expected_errors.into_iter() {
+            match Query::parse(query) {
+                Err(e) => assert_eq!(e.to_string(), expected_error, "{}", query),
+                Ok(_) => panic!("Error expected: {}", query)
+            }
+        }
+    }
+
+    #[test]
+    fn should_parse_functions_with_optional_arguments_and_conditions() {
+        let query = Query::parse("PROJECT SUBSTR(a, 2), LPAD(b, 5, '0') FILTER STARTS_WITH(a, 'x') AND NOT STARTS_WITH(b, 'y') OR STARTS_WITH(c, 'z') = 0").unwrap();
+        assert_eq!(query.projection[0], ProjectionItem::Expression(Expression::Function {
+            function: Function::Substr,
+            arguments: vec![Expression::Column("a".to_string()), Expression::Literal(Value::Integer(2))]
+        }));
+        assert_eq!(query.filter.as_ref().unwrap().to_string(), "STARTS_WITH(a, 'x') AND NOT STARTS_WITH(b, 'y') OR STARTS_WITH(c, 'z') = 0");
+        match query.filter.unwrap() {
+            Predicate::Or(left, _) => assert_eq!(left.conjuncts()[0], &Predicate::Expression(Expression::Function {
+                function: Function::StartsWith,
+                arguments: vec![Expression::Column("a".to_string()), Expression::Literal(Value::Text("x".to_string()))]
+            })),
+            predicate => panic!("Unexpected predicate {}", predicate)
+        }
+        match Query::parse("PROJECT SUBSTR(a) FROM t") {
+            Err(e) => assert_eq!(e.to_string(), "function SUBSTR expects 2 to 3 argument(s), found 1\nPROJECT SUBSTR(a) FROM t\n        ^^^^^^^^^"),
+            Ok(_) => panic!("Error expected")
+        }
+        match Query::parse("PROJECT a FILTER UPPER(a)") {
+            Err(e) => assert_eq!(e.to_string(), "expected comparison operator, found end of query\nPROJECT a FILTER UPPER(a)\n                         ^"),
+            Ok(_) => panic!("Error expected")
+        }
+    }
 }
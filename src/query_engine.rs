@@ -1,12 +1,17 @@
 use std::cmp::Ordering;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
 use std::fmt;
-use anyhow::{Result, Error};
-use crate::table::{IndexedTable, Index};
-use crate::query::{FilterType, Filter, Query};
-use crate::value::Value;
+use anyhow::{anyhow, Result, Error};
+use crate::database::IndexedDatabase;
+use crate::table::{Column, ColumnType, IndexedTable, Index, Row, Table, TableIndices, ValueInRow};
+use crate::query::{like_pattern_prefix, Aggregate, AggregateFunction, BinaryOperator, Expression, FilterType, Filter, JoinType, Limit, NullsOrder, OrderBy, Predicate, ProjectionItem, Query, SortDirection};
+use crate::value::{ArithmeticError, Value};
 
+// The names of the columns are those of the projected items, with all the columns
+// and the columns matching a pattern expanded to the names of the table columns
 #[derive(Debug, PartialEq)]
 pub struct ResultSet {
+    pub columns: Vec<String>,
     pub rows: Vec<ResultSetRow>
 }
 
@@ -23,112 +28,1457 @@ impl fmt::Display for ResultSetRow {
     }
 }
 
+// How an overflow, a division by zero or an argument outside of the domain of a math function
+// is handled: either the whole query fails or the value of the failed expression is NULL
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum ArithmeticErrors {
+    #[default]
+    Fail,
+    Null
+}
+
+impl ArithmeticErrors {
+    pub fn from(input: &str) -> Option<ArithmeticErrors> {
+        match input {
+            "fail" => Some(ArithmeticErrors::Fail),
+            "null" => Some(ArithmeticErrors::Null),
+            _ => None
+        }
+    }
+}
+
+// The handling of the arithmetic errors applies both to the computed expressions and to the aggregates
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ExecutionOptions {
+    pub arithmetic_errors: ArithmeticErrors
+}
+
+impl ExecutionOptions {
+    fn produces_null_for(&self, error: &Error) -> bool {
+        error.is::<ArithmeticError>() && self.arithmetic_errors == ArithmeticErrors::Null
+    }
+}
+
+// Executes the query against a single table, which the table given in FROM (if any) is taken to be
 pub fn execute(query: &Query, table: &IndexedTable) -> Result<ResultSet, Error> {
-    let row_ids = if let Some(filter) = &query.filter {
-        apply_filter(table, filter)?
+    if !query.joins.is_empty() {
+        return Err(anyhow!("Cannot join tables when querying a single table, the tables have to be added to a database"));
+    }
+    execute_on_single_table(query, table, &ExecutionOptions::default())
+}
+
+pub fn execute_on_database(query: &Query, database: &IndexedDatabase) -> Result<ResultSet, Error> {
+    execute_on_database_with_options(query, database, &ExecutionOptions::default())
+}
+
+pub fn execute_on_database_with_options(query: &Query, database: &IndexedDatabase, options: &ExecutionOptions) -> Result<ResultSet, Error> {
+    if !query.joins.is_empty() {
+        return execute_with_joins(query, database, options);
+    }
+    execute_on_single_table(query, database.find_table(query.from.as_deref())?, options)
+}
+
+fn execute_on_single_table(query: &Query, table: &IndexedTable, options: &ExecutionOptions) -> Result<ResultSet, Error> {
+    // Column names can be qualified with the name of the queried table
+    let query = match &query.from {
+        Some(table_name) => {
+            let qualifier = format!("{}.", table_name);
+            query.map_column_names(&|column_name| Ok(column_name.strip_prefix(&qualifier).unwrap_or(column_name).to_string()))?
+        },
+        None => query.clone()
+    };
+    check_types(&query, &|column_name| table.underlying.columns.iter()
+        .find(|column| column.name == column_name)
+        .map(|column| column.column_type.clone()))?;
+    execute_on_table(&query, table, options)
+}
+
+// The types of the arguments of the functions in all the clauses are checked against the types
+// of the columns before any row is looked at
+fn check_types(query: &Query, column_type: &dyn Fn(&str) -> Option<ColumnType>) -> Result<(), Error> {
+    for item in query.projection.iter() {
+        if let ProjectionItem::Expression(expression) = item.unaliased() {
+            infer_type(expression, column_type)?;
+        }
+    }
+    for predicate in query.filter.iter().chain(query.having.iter()) {
+        check_predicate_types(predicate, column_type)?;
+    }
+    for expression in query.group_by.iter().chain(query.order_by.iter().map(|key| &key.expression)) {
+        infer_type(expression, column_type)?;
+    }
+    Ok(())
+}
+
+fn check_predicate_types(predicate: &Predicate, column_type: &dyn Fn(&str) -> Option<ColumnType>) -> Result<(), Error> {
+    match predicate {
+        Predicate::Filter(filter) if !filter.filter_type.is_pattern_match() =>
+            check_not_compared_with_text(&filter.column_name, column_type(&filter.column_name), &[&filter.value])?,
+        Predicate::Comparison { left, filter_type, right } => {
+            let left_type = infer_type(left, column_type)?;
+            let right_type = infer_type(right, column_type)?;
+            if !filter_type.is_pattern_match() {
+                for (compared, compared_type, other) in [(left, left_type, right), (right, right_type, left)] {
+                    if let Expression::Literal(value) = other {
+                        check_not_compared_with_text(compared, compared_type, &[value])?;
+                    }
+                }
+            }
+        },
+        Predicate::In { operand, values, .. } =>
+            check_not_compared_with_text(operand, infer_type(operand, column_type)?, &values.iter().collect::<Vec<&Value>>())?,
+        Predicate::Between { operand, low, high, .. } =>
+            check_not_compared_with_text(operand, infer_type(operand, column_type)?, &[low, high])?,
+        Predicate::RegexMatch { left, .. } | Predicate::Expression(left) | Predicate::IsNull { operand: left, .. } => {
+            infer_type(left, column_type)?;
+        },
+        Predicate::And(left, right) | Predicate::Or(left, right) => {
+            check_predicate_types(left, column_type)?;
+            check_predicate_types(right, column_type)?;
+        },
+        Predicate::Not(negated) => check_predicate_types(negated, column_type)?,
+        Predicate::Filter(_) => ()
+    }
+    Ok(())
+}
+
+// A date or a timestamp is ordered before any text, so comparing it with a string is never what is meant
+fn check_not_compared_with_text(compared: &dyn fmt::Display, compared_type: Option<ColumnType>, values: &[&Value]) -> Result<(), Error> {
+    let text = values.iter().find(|value| matches!(value, Value::Text(_)));
+    match (compared_type, text) {
+        (Some(compared_type @ (ColumnType::Date | ColumnType::Timestamp)), Some(text)) =>
+            Err(anyhow!("Cannot compare {} {} with text '{}', write the value as {} '{}'",
+                compared_type, compared, text, compared_type.to_string().to_uppercase(), text)),
+        _ => Ok(())
+    }
+}
+
+// The type is unknown (None) for NULL, for the aggregates and for the expressions combining values of different types
+fn infer_type(expression: &Expression, column_type: &dyn Fn(&str) -> Option<ColumnType>) -> Result<Option<ColumnType>, Error> {
+    let common_type = |types: Vec<Option<ColumnType>>| match types.split_first() {
+        Some((first, rest)) if rest.iter().all(|other| other == first) => first.clone(),
+        _ => None
+    };
+    Ok(match expression {
+        Expression::Column(column_name) => column_type(column_name),
+        Expression::Literal(value) => match value {
+            Value::Null => None,
+            Value::Integer(_) => Some(ColumnType::Integer),
+            Value::Float(_) => Some(ColumnType::Float),
+            Value::Date(_) => Some(ColumnType::Date),
+            Value::Timestamp(_) => Some(ColumnType::Timestamp),
+            Value::Interval(_) => None,
+            Value::Text(_) => Some(ColumnType::Text)
+        },
+        Expression::Negation(negated) => infer_type(negated, column_type)?,
+        Expression::Binary { operator, left, right } => {
+            let types = (infer_type(left, column_type)?, infer_type(right, column_type)?);
+            match (operator, types) {
+                (BinaryOperator::Concatenate, _) => Some(ColumnType::Text),
+                (_, (Some(ColumnType::Integer), Some(ColumnType::Integer))) => Some(ColumnType::Integer),
+                (_, (Some(ColumnType::Integer | ColumnType::Float), Some(ColumnType::Integer | ColumnType::Float))) => Some(ColumnType::Float),
+                _ => None
+            }
+        },
+        Expression::Function { function, arguments } => {
+            let mut argument_types: Vec<Option<ColumnType>> = Vec::new();
+            for argument in arguments.iter() {
+                argument_types.push(infer_type(argument, column_type)?);
+            }
+            function.check_argument_types(&argument_types)?
+        },
+        Expression::Case { branches, otherwise } => {
+            let mut result_types: Vec<Option<ColumnType>> = Vec::new();
+            for (condition, result) in branches.iter() {
+                check_predicate_types(condition, column_type)?;
+                result_types.push(infer_type(result, column_type)?);
+            }
+            if let Some(otherwise) = otherwise {
+                result_types.push(infer_type(otherwise, column_type)?);
+            }
+            common_type(result_types)
+        },
+        Expression::SimpleCase { operand, branches, otherwise } => {
+            infer_type(operand, column_type)?;
+            let mut result_types: Vec<Option<ColumnType>> = Vec::new();
+            for (value, result) in branches.iter() {
+                infer_type(value, column_type)?;
+                result_types.push(infer_type(result, column_type)?);
+            }
+            if let Some(otherwise) = otherwise {
+                result_types.push(infer_type(otherwise, column_type)?);
+            }
+            common_type(result_types)
+        },
+        Expression::Aggregate(_) => None
+    })
+}
+
+fn execute_on_table(query: &Query, table: &IndexedTable, options: &ExecutionOptions) -> Result<ResultSet, Error> {
+    // The distinct rows are only known after the projection, so they are limited only then
+    let limit = query.limit.as_ref().filter(|_| !query.distinct);
+    let row_limit = limit.map(|limit| limit.offset.saturating_add(limit.count));
+    // Without ordering and aggregation only the first matching rows are returned, so the selection can stop at them
+    let selection_limit = row_limit.filter(|_| query.order_by.is_empty() && !query.is_aggregation());
+    let selected_row_ids = if let Some(predicate) = &query.filter {
+        Some(select_rows(table, predicate, selection_limit, options)?.matching)
+    } else {
+        None
+    };
+    if query.is_aggregation() {
+        return aggregate_rows(table, selected_row_ids, query, options);
+    }
+    if query.distinct {
+        if let Some(result_set) = project_distinct_values_using_index(table, selected_row_ids.as_deref(), query) {
+            return Ok(ResultSet { rows: limit_rows(result_set.rows, &query.limit), ..result_set });
+        }
+    }
+    let row_ids: Box<dyn Iterator<Item = usize>> = if !query.order_by.is_empty() {
+        let order_by: Vec<OrderBy> = query.order_by.iter()
+            .map(|key| OrderBy { expression: resolve_alias(table, &query.projection, &key.expression), ..key.clone() })
+            .collect();
+        Box::new(order_rows(table, selected_row_ids, &order_by, row_limit, options)?.into_iter())
+    } else if let Some(selected_row_ids) = selected_row_ids {
+        Box::new(selected_row_ids.into_iter())
     } else {
-        (0..table.underlying.rows.len()).collect()
+        Box::new(0..table.underlying.rows.len())
+    };
+    let row_ids = match limit {
+        Some(limit) => Box::new(row_ids.skip(limit.offset).take(limit.count)),
+        None => row_ids
     };
-    project_rows(table, &row_ids, &query.column_names)
+    let result_set = project_rows(table, row_ids, &query.projection, options)?;
+    if query.distinct {
+        Ok(ResultSet { rows: limit_rows(distinct_rows(result_set.rows), &query.limit), ..result_set })
+    } else {
+        Ok(result_set)
+    }
 }
 
-fn apply_filter(table: &IndexedTable, filter: &Filter) -> Result<Vec<usize>, Error> {
+// A name which is not a column of the table can refer to a projected column or expression by its alias
+fn resolve_alias(table: &IndexedTable, projection: &[ProjectionItem], expression: &Expression) -> Expression {
+    match expression {
+        Expression::Column(name) if !table.underlying.columns.iter().any(|column| &column.name == name) =>
+            projection.iter()
+                .find_map(|item| match item {
+                    ProjectionItem::Aliased { item, alias } if alias == name => match item.as_ref() {
+                        ProjectionItem::Column(column_name) => Some(Expression::Column(column_name.to_string())),
+                        ProjectionItem::Expression(expression) => Some(expression.clone()),
+                        _ => None
+                    },
+                    _ => None
+                })
+                .unwrap_or_else(|| expression.clone()),
+        expression => expression.clone()
+    }
+}
+
+// The distinct values of a single projected indexed column are read from the index, in which
+// equal values are adjacent, so that every value is compared only with the preceding one
+fn project_distinct_values_using_index(table: &IndexedTable, selected_row_ids: Option<&[usize]>, query: &Query) -> Option<ResultSet> {
+    let (item, column_name) = match query.projection.as_slice() {
+        [item] => match item.unaliased() {
+            ProjectionItem::Column(column_name) => (item, column_name),
+            _ => return None
+        },
+        _ => return None
+    };
+    if query.order_by.iter().any(|key| !matches!(&key.expression, Expression::Column(key_column_name) if key_column_name == column_name)) {
+        return None;
+    }
+    let index = table.indices.column_indices.get(column_name)?;
+    let selected_row_set: Option<BTreeSet<usize>> = selected_row_ids.map(|row_ids| row_ids.iter().cloned().collect());
+    let mut values: Vec<Value> = Vec::new();
+    let mut previous_value: Option<&Value> = None;
+    for value_in_row in index.sorted_column_values.iter() {
+        let is_selected = selected_row_set.as_ref().is_none_or(|selected_row_set| selected_row_set.contains(&value_in_row.row_index));
+        if is_selected && previous_value != Some(value_in_row.value) {
+            values.push(value_in_row.value.clone());
+            previous_value = Some(value_in_row.value);
+        }
+    }
+    values.sort_by(|x, y| query.order_by.iter()
+        .map(|key| compare_for_ordering(x, y, key))
+        .find(|ordering| *ordering != Ordering::Equal)
+        .unwrap_or(Ordering::Equal));
+    Some(ResultSet {
+        columns: vec![item.name()],
+        rows: values.into_iter().map(|value| ResultSetRow { fields: vec![value] }).collect()
+    })
+}
+
+// Keeps the first of the equal rows
+fn distinct_rows(rows: Vec<ResultSetRow>) -> Vec<ResultSetRow> {
+    let mut seen_rows: HashSet<Vec<Value>> = HashSet::new();
+    rows.into_iter()
+        .filter(|row| seen_rows.insert(row.fields.clone()))
+        .collect()
+}
+
+fn limit_rows(rows: Vec<ResultSetRow>, limit: &Option<Limit>) -> Vec<ResultSetRow> {
+    match limit {
+        Some(limit) => rows.into_iter().skip(limit.offset).take(limit.count).collect(),
+        None => rows
+    }
+}
+
+// The joined rows are materialized as a single table with the columns named "table.column", which
+// is then queried like any other table. The conditions of the filter referring only to the columns
+// of the first table are applied before joining, so that they can use the indices of that table.
+fn execute_with_joins(query: &Query, database: &IndexedDatabase, options: &ExecutionOptions) -> Result<ResultSet, Error> {
+    let base_table_name = query.from.as_deref()
+        .ok_or_else(|| anyhow!("Table to join with has to be specified with FROM"))?;
+    let mut tables: Vec<(&str, &IndexedTable)> = vec![(base_table_name, database.find_table(Some(base_table_name))?)];
+    for join in query.joins.iter() {
+        if tables.iter().any(|(table_name, _)| *table_name == join.table_name) {
+            return Err(anyhow!("Table {} is used more than once in the query", join.table_name));
+        }
+        tables.push((&join.table_name, database.find_table(Some(&join.table_name))?));
+    }
+    let query = query.map_column_names(&|column_name| qualify_column_name(column_name, &tables))?;
+    check_types(&query, &|column_name| locate_column(column_name, &tables).ok()
+        .map(|(table_position, column_position)| tables[table_position].1.underlying.columns[column_position].column_type.clone()))?;
+    let base_qualifier = format!("{}.", base_table_name);
+    let (base_conditions, remaining_conditions): (Vec<&Predicate>, Vec<&Predicate>) = query.filter.iter()
+        .flat_map(|filter| filter.conjuncts())
+        .partition(|condition| condition.column_names().iter().all(|column_name| column_name.starts_with(&base_qualifier)));
+    let base_table = tables[0].1;
+    let mut joined_rows: Vec<Vec<Option<usize>>> = match Predicate::conjunction(base_conditions.into_iter().cloned().collect()) {
+        Some(base_filter) => {
+            let base_filter = base_filter.map_column_names(&|column_name| Ok(column_name[base_qualifier.len()..].to_string()))?;
+            select_rows(base_table, &base_filter, None, options)?.matching.into_iter().map(|row_id| vec![Some(row_id)]).collect()
+        },
+        None => (0..base_table.underlying.rows.len()).map(|row_id| vec![Some(row_id)]).collect()
+    };
+    for (join_position, join) in query.joins.iter().enumerate() {
+        let inner_table_position = join_position + 1;
+        let ((outer_table_position, outer_column_position), (_, inner_column_position)) = {
+            let left = locate_column(&join.left_column_name, &tables)?;
+            let right = locate_column(&join.right_column_name, &tables)?;
+            if right.0 == inner_table_position && left.0 < inner_table_position {
+                (left, right)
+            } else if left.0 == inner_table_position && right.0 < inner_table_position {
+                (right, left)
+            } else {
+                return Err(anyhow!("Cannot join table {} on {} = {}, a column of it has to be compared with a column of a preceding table",
+                    join.table_name, join.left_column_name, join.right_column_name));
+            }
+        };
+        let (outer_table, inner_table) = (tables[outer_table_position].1, tables[inner_table_position].1);
+        let outer_values: Vec<Option<&Value>> = joined_rows.iter()
+            .map(|joined_row| joined_row[outer_table_position].map(|row_id| &outer_table.underlying.rows[row_id].fields[outer_column_position]))
+            .collect();
+        let inner_row_ids = find_joined_rows(&outer_values, inner_table, inner_column_position);
+        joined_rows = joined_rows.into_iter().zip(inner_row_ids)
+            .flat_map(|(joined_row, inner_row_ids)| {
+                let inner_row_ids: Vec<Option<usize>> = if inner_row_ids.is_empty() && join.join_type == JoinType::LeftOuter {
+                    vec![None]
+                } else {
+                    inner_row_ids.into_iter().map(Some).collect()
+                };
+                inner_row_ids.into_iter().map(move |inner_row_id| {
+                    let mut joined_row = joined_row.clone();
+                    joined_row.push(inner_row_id);
+                    joined_row
+                })
+            })
+            .collect();
+    }
+    let joined_table = materialize_joined_rows(&tables, joined_rows);
+    let indexed_joined_table = IndexedTable {
+        underlying: &joined_table,
+        indices: TableIndices {
+            column_indices: HashMap::new()
+        }
+    };
+    let remaining_query = Query {
+        filter: Predicate::conjunction(remaining_conditions.into_iter().cloned().collect()),
+        ..query.clone()
+    };
+    execute_on_table(&remaining_query, &indexed_joined_table, options)
+}
+
+// A column name is either qualified as "table.column" or refers to the only table having such a column
+fn qualify_column_name(column_name: &str, tables: &[(&str, &IndexedTable)]) -> Result<String, Error> {
+    if let Some((table_name, qualified_column_name)) = column_name.split_once('.') {
+        if let Some((_, table)) = tables.iter().find(|(name, _)| *name == table_name) {
+            table.underlying.find_column_position(qualified_column_name)?;
+            return Ok(column_name.to_string());
+        }
+    }
+    let table_names: Vec<&str> = tables.iter()
+        .filter(|(_, table)| table.underlying.find_column_position(column_name).is_ok())
+        .map(|(table_name, _)| *table_name)
+        .collect();
+    match table_names.as_slice() {
+        [table_name] => Ok(format!("{}.{}", table_name, column_name)),
+        [] => Err(anyhow!("Cannot find column {}, it does not exist in the tables {}",
+            column_name,
+            tables.iter().map(|(table_name, _)| *table_name).collect::<Vec<&str>>().join(", "))),
+        _ => Err(anyhow!("Column {} is ambiguous, it exists in the tables {}", column_name, table_names.join(", ")))
+    }
+}
+
+// Finds the positions of the table and of the column in it for an already qualified column name
+fn locate_column(column_name: &str, tables: &[(&str, &IndexedTable)]) -> Result<(usize, usize), Error> {
+    let (table_name, column_name) = column_name.split_once('.')
+        .ok_or_else(|| anyhow!("Column {} is not qualified with a table name", column_name))?;
+    let table_position = tables.iter().position(|(name, _)| *name == table_name)
+        .ok_or_else(|| anyhow!("Cannot find table {} among the joined tables", table_name))?;
+    Ok((table_position, tables[table_position].1.underlying.find_column_position(column_name)?))
+}
+
+// For every outer value finds the inner rows with an equal value of the join column, NULL values
+// never match. When there are few outer values compared to the size of the inner table, the index
+// of the inner column is searched for each of them, otherwise a hash table of the inner rows is built.
+fn find_joined_rows(outer_values: &[Option<&Value>], inner_table: &IndexedTable, inner_column_position: usize) -> Vec<Vec<usize>> {
+    let inner_column_name = &inner_table.underlying.columns[inner_column_position].name;
+    let inner_size = inner_table.underlying.rows.len();
+    let outer_size = outer_values.len();
+    let index_lookup_cost = outer_size * (usize::BITS - inner_size.leading_zeros()) as usize;
+    match inner_table.indices.column_indices.get(inner_column_name).filter(|_| index_lookup_cost <= inner_size + outer_size) {
+        Some(index) => outer_values.iter()
+            .map(|outer_value| match outer_value {
+                Some(outer_value) if !outer_value.is_null() => {
+                    let values = &index.sorted_column_values;
+                    let first_idx_not_less_than = values.partition_point(|value_in_row| value_in_row.value < outer_value);
+                    let first_idx_greater_than = values.partition_point(|value_in_row| value_in_row.value <= outer_value);
+                    values[first_idx_not_less_than..first_idx_greater_than].iter().map(|value_in_row| value_in_row.row_index).collect()
+                },
+                _ => Vec::new()
+            })
+            .collect(),
+        None => {
+            let mut inner_rows_by_value: HashMap<&Value, Vec<usize>> = HashMap::new();
+            for (row_id, row) in inner_table.underlying.rows.iter().enumerate() {
+                let value = &row.fields[inner_column_position];
+                if !value.is_null() {
+                    inner_rows_by_value.entry(value).or_default().push(row_id);
+                }
+            }
+            outer_values.iter()
+                .map(|outer_value| outer_value
+                    .and_then(|outer_value| inner_rows_by_value.get(outer_value))
+                    .cloned()
+                    .unwrap_or_default())
+                .collect()
+        }
+    }
+}
+
+// A missing row of a table (in a LEFT OUTER JOIN) contributes NULL values for all of its columns
+fn materialize_joined_rows(tables: &[(&str, &IndexedTable)], joined_rows: Vec<Vec<Option<usize>>>) -> Table {
+    let columns = tables.iter()
+        .flat_map(|(table_name, table)| table.underlying.columns.iter().map(move |column| Column {
+            name: format!("{}.{}", table_name, column.name),
+            column_type: column.column_type.clone()
+        }))
+        .collect();
+    let rows = joined_rows.into_iter()
+        .map(|joined_row| Row {
+            fields: joined_row.into_iter().zip(tables.iter())
+                .flat_map(|(row_id, (_, table))| match row_id {
+                    Some(row_id) => table.underlying.rows[row_id].fields.clone(),
+                    None => vec![Value::Null; table.underlying.columns.len()]
+                })
+                .collect()
+        })
+        .collect();
+    Table {
+        columns,
+        rows
+    }
+}
+
+// Predicates follow the SQL three-valued logic: a comparison involving NULL is neither true nor
+// false but unknown, so next to the matching rows the rows for which the result is unknown are
+// tracked as well, negating an unknown result still produces an unknown result
+struct Selection {
+    matching: Vec<usize>,
+    unknown: BTreeSet<usize>
+}
+
+// Every condition is answered on its own (using the index when available) and the resulting
+// row ids are then combined as sets, so a combined predicate never falls back to a full scan.
+// With a row limit, which is only given for the whole filter, only as many matching rows are selected.
+fn select_rows(table: &IndexedTable, predicate: &Predicate, row_limit: Option<usize>, options: &ExecutionOptions) -> Result<Selection, Error> {
+    match predicate {
+        Predicate::Filter(filter) =>
+            apply_filter(table, filter, row_limit),
+        Predicate::Comparison { .. } | Predicate::RegexMatch { .. } | Predicate::Expression(_) =>
+            select_by_evaluating(table, predicate, row_limit, options),
+        Predicate::IsNull { operand: Expression::Column(column_name), negated } =>
+            apply_null_check(table, column_name, *negated, row_limit),
+        Predicate::IsNull { .. } =>
+            select_by_evaluating(table, predicate, row_limit, options),
+        Predicate::In { operand, values, negated } =>
+            match index_of(table, operand) {
+                Some(index) => {
+                    let mut distinct_values: Vec<&Value> = values.iter().collect();
+                    distinct_values.sort();
+                    distinct_values.dedup();
+                    Ok(select_ranges_using_index(index, distinct_values.into_iter().map(|value| (value, value)).collect(), *negated, row_limit))
+                },
+                None => select_by_evaluating(table, predicate, row_limit, options)
+            },
+        Predicate::Between { operand, low, high, negated } =>
+            match index_of(table, operand) {
+                Some(index) => Ok(select_ranges_using_index(index, vec![(low, high)], *negated, row_limit)),
+                None => select_by_evaluating(table, predicate, row_limit, options)
+            },
+        Predicate::And(left, right) => {
+            let left_selection = select_rows(table, left, None, options)?;
+            if left_selection.matching.is_empty() && left_selection.unknown.is_empty() {
+                Ok(left_selection)
+            } else {
+                let right_selection = select_rows(table, right, None, options)?;
+                let left_matching: BTreeSet<usize> = left_selection.matching.into_iter().collect();
+                let right_matching: BTreeSet<usize> = right_selection.matching.into_iter().collect();
+                let matching: BTreeSet<usize> = left_matching.intersection(&right_matching).cloned().collect();
+                let left_not_false: BTreeSet<usize> = left_matching.union(&left_selection.unknown).cloned().collect();
+                let right_not_false: BTreeSet<usize> = right_matching.union(&right_selection.unknown).cloned().collect();
+                let unknown = left_not_false.intersection(&right_not_false)
+                    .filter(|row_id| !matching.contains(row_id))
+                    .cloned()
+                    .collect();
+                Ok(Selection {
+                    matching: matching.into_iter().take(row_limit.unwrap_or(usize::MAX)).collect(),
+                    unknown
+                })
+            }
+        },
+        Predicate::Or(left, right) => {
+            let left_selection = select_rows(table, left, None, options)?;
+            let right_selection = select_rows(table, right, None, options)?;
+            let matching: BTreeSet<usize> = left_selection.matching.into_iter()
+                .chain(right_selection.matching)
+                .collect();
+            let unknown = left_selection.unknown.union(&right_selection.unknown)
+                .filter(|row_id| !matching.contains(row_id))
+                .cloned()
+                .collect();
+            Ok(Selection {
+                matching: matching.into_iter().take(row_limit.unwrap_or(usize::MAX)).collect(),
+                unknown
+            })
+        },
+        Predicate::Not(negated) => {
+            let negated_selection = select_rows(table, negated, None, options)?;
+            let negated_matching: BTreeSet<usize> = negated_selection.matching.into_iter().collect();
+            Ok(Selection {
+                matching: (0..table.underlying.rows.len())
+                    .filter(|row_id| !negated_matching.contains(row_id) && !negated_selection.unknown.contains(row_id))
+                    .take(row_limit.unwrap_or(usize::MAX))
+                    .collect(),
+                unknown: negated_selection.unknown
+            })
+        }
+    }
+}
+
+fn apply_filter(table: &IndexedTable, filter: &Filter, row_limit: Option<usize>) -> Result<Selection, Error> {
     if let Some(column_index) = table.indices.column_indices.get(&filter.column_name) {
-        filter_using_index(filter, column_index)
+        filter_using_index(filter, column_index, row_limit)
+    } else {
+        filter_by_scanning(table, filter, row_limit)
+    }
+}
+
+// A predicate which cannot be answered using an index is evaluated for every row
+fn select_by_evaluating(table: &IndexedTable, predicate: &Predicate, row_limit: Option<usize>, options: &ExecutionOptions) -> Result<Selection, Error> {
+    let column_positions = find_column_positions(table, predicate.column_names())?;
+    let mut matching: Vec<usize> = Vec::new();
+    let mut unknown: BTreeSet<usize> = BTreeSet::new();
+    for (row_id, row) in table.underlying.rows.iter().enumerate() {
+        if row_limit.is_some_and(|row_limit| matching.len() >= row_limit) {
+            break;
+        }
+        match evaluate_predicate(predicate, &|column_name| value_in_row(row, &column_positions, column_name), options)? {
+            Some(true) => matching.push(row_id),
+            Some(false) => {},
+            None => {
+                unknown.insert(row_id);
+            }
+        }
+    }
+    Ok(Selection {
+        matching,
+        unknown
+    })
+}
+
+// The positions of the columns used in expressions are found once before evaluating them for every row
+fn find_column_positions<'a>(table: &IndexedTable, column_names: Vec<&'a str>) -> Result<HashMap<&'a str, usize>, Error> {
+    let mut column_positions: HashMap<&str, usize> = HashMap::new();
+    for column_name in column_names.into_iter() {
+        column_positions.insert(column_name, table.underlying.find_column_position(column_name)?);
+    }
+    Ok(column_positions)
+}
+
+// Only a plain column can be looked up in an index
+fn index_of<'a>(table: &'a IndexedTable, expression: &Expression) -> Option<&'a Index<'a>> {
+    match expression {
+        Expression::Column(column_name) => table.indices.column_indices.get(column_name),
+        _ => None
+    }
+}
+
+fn value_in_row<'a>(row: &'a Row, column_positions: &HashMap<&str, usize>, reference: Reference) -> Result<&'a Value, Error> {
+    match reference {
+        Reference::Column(column_name) => column_positions.get(column_name)
+            .map(|&column_position| &row.fields[column_position])
+            .ok_or_else(|| anyhow!("Cannot find column {}", column_name)),
+        Reference::Aggregate(aggregate) =>
+            Err(anyhow!("Aggregate {} can only be used in HAVING and ORDER BY of the aggregated rows", aggregate))
+    }
+}
+
+fn apply_null_check(table: &IndexedTable, column_name: &str, negated: bool, row_limit: Option<usize>) -> Result<Selection, Error> {
+    let matching = if let Some(index) = table.indices.column_indices.get(column_name) {
+        let first_idx_not_null = first_idx_not_null(index);
+        let matching_values = if negated {
+            &index.sorted_column_values[first_idx_not_null..]
+        } else {
+            &index.sorted_column_values[..first_idx_not_null]
+        };
+        matching_values.iter().take(row_limit.unwrap_or(usize::MAX)).map(|value_in_row| value_in_row.row_index).collect()
     } else {
-        filter_by_scanning(table, filter)
+        let column_position = table.underlying.find_column_position(column_name)?;
+        table.underlying.rows.iter().enumerate()
+            .filter(|(_, row)| row.fields[column_position].is_null() != negated)
+            .take(row_limit.unwrap_or(usize::MAX))
+            .map(|(row_id, _)| row_id)
+            .collect()
+    };
+    Ok(Selection {
+        matching,
+        unknown: BTreeSet::new()
+    })
+}
+
+// When the first sort key is indexed, the index already yields the rows in the requested order,
+// only the rows sharing the same value of the first key then still need to be sorted by the
+// remaining keys. Walking the index is only worth it when most of the rows are selected.
+// With a row limit only the first rows are ordered, the rest of the rows is never sorted.
+fn order_rows(table: &IndexedTable, selected_row_ids: Option<Vec<usize>>, order_by: &[OrderBy], row_limit: Option<usize>, options: &ExecutionOptions) -> Result<Vec<usize>, Error> {
+    let mut sort_keys: Vec<(SortKey, &OrderBy)> = Vec::new();
+    for key in order_by.iter() {
+        let sort_key = match &key.expression {
+            Expression::Column(column_name) => SortKey::Column(table.underlying.find_column_position(column_name)?),
+            expression => SortKey::Computed(evaluate_for_rows(table, expression, selected_row_ids.as_deref(), options)?)
+        };
+        sort_keys.push((sort_key, key));
+    }
+    let rows = &table.underlying.rows;
+    let compare_rows = |x: &usize, y: &usize, keys: &[(SortKey, &OrderBy)]| {
+        keys.iter()
+            .map(|(sort_key, key)| compare_for_ordering(sort_key.value(rows, *x), sort_key.value(rows, *y), key))
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or_else(|| x.cmp(y))
+    };
+    let table_size = table.underlying.rows.len();
+    let is_most_of_table_selected = selected_row_ids.as_ref()
+        .map(|row_ids| row_ids.len() * (usize::BITS - row_ids.len().leading_zeros()) as usize >= table_size)
+        .unwrap_or(true);
+    let first_key = &order_by[0];
+    let first_key_index = index_of(table, &first_key.expression);
+    match first_key_index.filter(|_| is_most_of_table_selected) {
+        Some(index) => {
+            let selected_row_ids: Option<BTreeSet<usize>> = selected_row_ids.map(|row_ids| row_ids.into_iter().collect());
+            let (null_values, values) = index.sorted_column_values.split_at(first_idx_not_null(index));
+            let mut value_groups: Vec<&[ValueInRow]> = values.chunk_by(|x, y| x.value == y.value).collect();
+            if first_key.direction == SortDirection::Descending {
+                value_groups.reverse();
+            }
+            if !null_values.is_empty() {
+                match first_key.nulls {
+                    NullsOrder::First => value_groups.insert(0, null_values),
+                    NullsOrder::Last => value_groups.push(null_values)
+                }
+            }
+            let mut row_ids: Vec<usize> = Vec::new();
+            for value_group in value_groups.into_iter() {
+                if row_limit.is_some_and(|row_limit| row_ids.len() >= row_limit) {
+                    break;
+                }
+                let mut group_row_ids: Vec<usize> = value_group.iter()
+                    .map(|value_in_row| value_in_row.row_index)
+                    .filter(|row_id| selected_row_ids.as_ref().map(|selected| selected.contains(row_id)).unwrap_or(true))
+                    .collect();
+                group_row_ids.sort_by(|x, y| compare_rows(x, y, &sort_keys[1..]));
+                row_ids.extend(group_row_ids);
+            }
+            Ok(row_ids)
+        },
+        None => {
+            let row_ids: Box<dyn Iterator<Item = usize>> = match selected_row_ids {
+                Some(selected_row_ids) => Box::new(selected_row_ids.into_iter()),
+                None => Box::new(0..table_size)
+            };
+            match row_limit {
+                Some(row_limit) => Ok(top_rows(table, row_ids, &sort_keys, row_limit)),
+                None => {
+                    let mut row_ids: Vec<usize> = row_ids.collect();
+                    row_ids.sort_by(|x, y| compare_rows(x, y, &sort_keys));
+                    Ok(row_ids)
+                }
+            }
+        }
     }
 }
 
-fn project_rows(table: &IndexedTable, row_ids: &Vec<usize>, column_names: &Vec<String>) -> Result<ResultSet, Error> {
-    let mut column_positions: Vec<usize> = Vec::new();
-    for column_name in column_names.iter() {
-        let column_position = table.underlying.find_column_position(&column_name)?;
-        column_positions.push(column_position);
+// A sort key is either a column of the table or an expression computed for the ordered rows
+enum SortKey {
+    Column(usize),
+    Computed(Vec<Value>)
+}
+
+impl SortKey {
+    fn value<'a>(&'a self, rows: &'a [Row], row_id: usize) -> &'a Value {
+        match self {
+            SortKey::Column(column_position) => &rows[row_id].fields[*column_position],
+            SortKey::Computed(values) => &values[row_id]
+        }
     }
+}
+
+// The values are indexed by row, only the given rows are evaluated and the rest are left NULL
+fn evaluate_for_rows(table: &IndexedTable, expression: &Expression, row_ids: Option<&[usize]>, options: &ExecutionOptions) -> Result<Vec<Value>, Error> {
+    let column_positions = find_column_positions(table, expression.column_names())?;
+    let rows = &table.underlying.rows;
+    let mut values: Vec<Value> = vec![Value::Null; rows.len()];
+    let row_ids: Box<dyn Iterator<Item = usize>> = match row_ids {
+        Some(row_ids) => Box::new(row_ids.iter().cloned()),
+        None => Box::new(0..rows.len())
+    };
+    for row_id in row_ids {
+        values[row_id] = evaluate_expression(expression, &|column_name| value_in_row(&rows[row_id], &column_positions, column_name), options)?;
+    }
+    Ok(values)
+}
+
+// Keeps the row_limit smallest rows seen so far in a max-heap, so that the largest of them can
+// be evicted as soon as a smaller row is found
+fn top_rows<'a>(table: &'a IndexedTable, row_ids: impl Iterator<Item = usize>, sort_keys: &'a [(SortKey, &'a OrderBy)], row_limit: usize) -> Vec<usize> {
+    // The limit given in the query can be far larger than the table
+    let mut heap: BinaryHeap<RowToOrder> = BinaryHeap::with_capacity(row_limit.min(table.underlying.rows.len()) + 1);
+    for row_id in row_ids {
+        heap.push(RowToOrder {
+            row_id,
+            sort_values: sort_keys.iter().map(|(sort_key, _)| sort_key.value(&table.underlying.rows, row_id)).collect(),
+            sort_keys
+        });
+        if heap.len() > row_limit {
+            heap.pop();
+        }
+    }
+    heap.into_sorted_vec().into_iter().map(|row| row.row_id).collect()
+}
+
+struct RowToOrder<'a> {
+    row_id: usize,
+    sort_values: Vec<&'a Value>,
+    sort_keys: &'a [(SortKey, &'a OrderBy)]
+}
+
+impl Ord for RowToOrder<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_values.iter().zip(other.sort_values.iter()).zip(self.sort_keys.iter())
+            .map(|((x, y), (_, key))| compare_for_ordering(x, y, key))
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or_else(|| self.row_id.cmp(&other.row_id))
+    }
+}
+
+impl PartialOrd for RowToOrder<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for RowToOrder<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for RowToOrder<'_> {}
+
+fn compare_for_ordering(x: &Value, y: &Value, key: &OrderBy) -> Ordering {
+    match (x.is_null(), y.is_null(), key.nulls) {
+        (true, true, _) => Ordering::Equal,
+        (true, false, NullsOrder::First) | (false, true, NullsOrder::Last) => Ordering::Less,
+        (true, false, NullsOrder::Last) | (false, true, NullsOrder::First) => Ordering::Greater,
+        (false, false, _) => match key.direction {
+            SortDirection::Ascending => x.cmp(y),
+            SortDirection::Descending => x.cmp(y).reverse()
+        }
+    }
+}
+
+fn project_rows(table: &IndexedTable, row_ids: impl Iterator<Item = usize>, projection: &[ProjectionItem], options: &ExecutionOptions) -> Result<ResultSet, Error> {
+    let mut columns: Vec<String> = Vec::new();
+    let mut fields: Vec<ProjectedField> = Vec::new();
+    for item in projection.iter() {
+        add_projected_fields(table, item, &mut columns, &mut fields)?;
+    }
+    let expression_column_names = fields.iter()
+        .flat_map(|field| match field {
+            ProjectedField::Computed(expression) => expression.column_names(),
+            ProjectedField::Column(_) => Vec::new()
+        })
+        .collect();
+    let expression_column_positions = find_column_positions(table, expression_column_names)?;
     let mut rows: Vec<ResultSetRow> = Vec::new();
-    for row_id in row_ids.into_iter() {
-        let projected_row = &table.underlying.rows[*row_id];
-        let row_projection: Vec<Value> = column_positions.iter()
-            .map(|&column_position| projected_row.fields[column_position].clone())
-            .collect();
+    for row_id in row_ids {
+        let projected_row = &table.underlying.rows[row_id];
+        let value_of = |reference: Reference<'_>| value_in_row(projected_row, &expression_column_positions, reference);
+        let mut row_projection: Vec<Value> = Vec::new();
+        for field in fields.iter() {
+            row_projection.push(match field {
+                ProjectedField::Column(column_position) => projected_row.fields[*column_position].clone(),
+                ProjectedField::Computed(expression) => evaluate_expression(expression, &value_of, options)?
+            });
+        }
         rows.push(ResultSetRow {
             fields: row_projection
         });
     }
-    Ok(ResultSet { rows })
+    Ok(ResultSet { columns, rows })
 }
 
-fn filter_using_index(filter: &Filter, index: &Index<'_>) -> Result<Vec<usize>, Error> {
-    match filter.filter_type {
-        FilterType::Greater => {
-            filter_using_index_greater_than(&filter.value, index)
+// A projected field is either copied from a column of the row or computed from an expression
+enum ProjectedField<'a> {
+    Column(usize),
+    Computed(&'a Expression)
+}
+
+fn add_projected_fields<'a>(table: &IndexedTable, item: &'a ProjectionItem, columns: &mut Vec<String>, fields: &mut Vec<ProjectedField<'a>>) -> Result<(), Error> {
+    let column_positions: Vec<usize> = match item {
+        ProjectionItem::Column(column_name) =>
+            vec![table.underlying.find_column_position(column_name)?],
+        ProjectionItem::AllColumns { except } => {
+            let mut excluded_positions: Vec<usize> = Vec::new();
+            for column_name in except.iter() {
+                excluded_positions.push(table.underlying.find_column_position(column_name)?);
+            }
+            (0..table.underlying.columns.len()).filter(|position| !excluded_positions.contains(position)).collect()
+        },
+        // The columns of joined tables are matched by their names qualified with the table name
+        ProjectionItem::MatchingColumns(pattern) =>
+            table.underlying.columns.iter().enumerate()
+                .filter(|(_, column)| pattern.is_match(&column.name))
+                .map(|(position, _)| position)
+                .collect(),
+        ProjectionItem::Expression(expression) => {
+            columns.push(item.name());
+            fields.push(ProjectedField::Computed(expression));
+            return Ok(());
+        },
+        // Only a single column or expression can be given an alias
+        ProjectionItem::Aliased { item, alias } => {
+            add_projected_fields(table, item, columns, fields)?;
+            if let Some(column) = columns.last_mut() {
+                *column = alias.to_string();
+            }
+            return Ok(());
         },
-        FilterType::Equal => {
-            filter_using_index_equal_to(&filter.value, index)
-        }
-    }
-}
-
-fn filter_using_index_greater_than(value: &Value, index: &Index<'_>) -> Result<Vec<usize>, Error> {
-    let mut row_ids: Vec<usize> = Vec::new();
-    let found_idx = match index.sorted_column_values
-        .binary_search_by(|value_in_row| {
-           if *value_in_row.value <= *value {
-               Ordering::Less
-           } else {
-               Ordering::Greater
-           }
-        }) {
-           Err(idx) =>
-               if idx < index.sorted_column_values.len() {
-                   Some(idx)
-               } else {
-                   None
-               }
-           _ => None
+        ProjectionItem::Aggregate(aggregate) =>
+            return Err(anyhow!("Aggregate {} cannot be projected without aggregating the rows", aggregate))
+    };
+    for column_position in column_positions.into_iter() {
+        columns.push(table.underlying.columns[column_position].name.to_string());
+        fields.push(ProjectedField::Column(column_position));
+    }
+    Ok(())
+}
+
+// Aggregates the selected rows into groups using a hash table keyed by the values of the
+// GROUP BY columns, without GROUP BY all the rows form a single group (even if there are none).
+// The projection, ORDER BY and LIMIT are then applied to the aggregated rows.
+fn aggregate_rows(table: &IndexedTable, selected_row_ids: Option<Vec<usize>>, query: &Query, options: &ExecutionOptions) -> Result<ResultSet, Error> {
+    let group_names: Vec<String> = query.group_by.iter().map(|expression| expression.to_string()).collect();
+    let group_expressions: Vec<Expression> = query.group_by.iter()
+        .map(|expression| resolve_alias(table, &query.projection, expression))
+        .collect();
+    // A grouped expression is found either by what it refers to or by how it is written in GROUP BY
+    let group_position = |expression: &Expression| group_expressions.iter().zip(group_names.iter())
+        .position(|(group_expression, group_name)| group_expression == expression || *group_name == expression.to_string());
+    let is_grouped_column = |column_name: &str| group_position(&Expression::Column(column_name.to_string())).is_some();
+    let mut aggregates: Vec<(&Aggregate, Option<usize>)> = Vec::new();
+    for item in query.projection.iter() {
+        match item.unaliased() {
+            ProjectionItem::Column(column_name) =>
+                if !is_grouped_column(column_name) {
+                    return Err(anyhow!("Column {} must appear in GROUP BY or be used in an aggregate", column_name));
+                },
+            // Expressions other than the grouped ones are computed from the grouped columns of every aggregated row
+            ProjectionItem::Expression(expression) =>
+                if group_position(expression).is_none() {
+                    if let Some(column_name) = expression.column_names().into_iter().find(|column_name| !is_grouped_column(column_name)) {
+                        return Err(anyhow!("Column {} must appear in GROUP BY or be used in an aggregate", column_name));
+                    }
+                },
+            ProjectionItem::AllColumns { .. } | ProjectionItem::MatchingColumns(_) | ProjectionItem::Aliased { .. } =>
+                return Err(anyhow!("Cannot project {} when aggregating the rows, only the grouped columns and aggregates can be projected", item)),
+            ProjectionItem::Aggregate(aggregate) => {
+                let argument_position = match &aggregate.column_name {
+                    Some(column_name) => Some(table.underlying.find_column_position(column_name)?),
+                    None => None
+                };
+                aggregates.push((aggregate, argument_position));
+            }
+        }
+    }
+    let aggregated_rows = if query.group_by.is_empty() {
+        vec![aggregate_all_rows(table, selected_row_ids, &aggregates, options)?]
+    } else {
+        let row_ids: Box<dyn Iterator<Item = usize>> = match selected_row_ids {
+            Some(selected_row_ids) => Box::new(selected_row_ids.into_iter()),
+            None => Box::new(0..table.underlying.rows.len())
+        };
+        aggregate_groups(table, row_ids, &group_expressions, &aggregates, options)?
+    };
+    let aggregated_rows = match &query.having {
+        Some(having) => {
+            let mut matching_rows: Vec<AggregatedRow> = Vec::new();
+            for aggregated_row in aggregated_rows.into_iter() {
+                let value_of = |reference: Reference<'_>| aggregated_row.value_of(reference, &group_names, &aggregates);
+                if evaluate_predicate(having, &value_of, options)? == Some(true) {
+                    matching_rows.push(aggregated_row);
+                }
+            }
+            matching_rows
+        },
+        None => aggregated_rows
+    };
+    let mut rows: Vec<ResultSetRow> = Vec::new();
+    for AggregatedRow { group_key, aggregate_values } in aggregated_rows.into_iter() {
+        let mut aggregate_values = aggregate_values.into_iter();
+        let value_of = |reference: Reference<'_>| match reference {
+            Reference::Column(column_name) => group_position(&Expression::Column(column_name.to_string()))
+                .map(|group_position| &group_key[group_position])
+                .ok_or_else(|| anyhow!("Column {} must appear in GROUP BY or be used in an aggregate", column_name)),
+            Reference::Aggregate(aggregate) =>
+                Err(anyhow!("Aggregate {} can only be projected on its own", aggregate))
+        };
+        let mut fields: Vec<Value> = Vec::new();
+        for item in query.projection.iter() {
+            fields.push(match item.unaliased() {
+                ProjectionItem::Column(column_name) => value_of(Reference::Column(column_name))?.clone(),
+                ProjectionItem::Expression(expression) => match group_position(expression) {
+                    Some(group_position) => group_key[group_position].clone(),
+                    None => evaluate_expression(expression, &value_of, options)?
+                },
+                _ => aggregate_values.next().unwrap_or(Value::Null)
+            });
+        }
+        rows.push(ResultSetRow { fields });
+    }
+    order_aggregated_rows(&mut rows, &query.projection, &query.order_by)?;
+    if query.distinct {
+        rows = distinct_rows(rows);
+    }
+    let rows = limit_rows(rows, &query.limit);
+    let columns = query.projection.iter().map(|item| item.name()).collect();
+    Ok(ResultSet { columns, rows })
+}
+
+struct AggregatedRow {
+    group_key: Vec<Value>,
+    aggregate_values: Vec<Value>
+}
+
+impl AggregatedRow {
+    fn value_of(&self, reference: Reference, group_by: &[String], aggregates: &[(&Aggregate, Option<usize>)]) -> Result<&Value, Error> {
+        let found = match reference {
+            Reference::Column(name) => group_by.iter().position(|column_name| column_name == name)
+                .map(|group_position| &self.group_key[group_position]),
+            Reference::Aggregate(aggregate) => aggregates.iter().position(|(computed, _)| *computed == aggregate)
+                .map(|aggregate_position| &self.aggregate_values[aggregate_position])
         };
-    if let Some(first_idx_greater_than) = found_idx {
-        row_ids = index.sorted_column_values[first_idx_greater_than..].iter().map(|value_in_row| value_in_row.row_index).collect();
+        found.ok_or_else(|| anyhow!("Cannot find {} among the grouped columns and aggregates", reference))
     }
-    Ok(row_ids)
 }
 
-fn filter_using_index_equal_to(value: &Value, index: &Index<'_>) -> Result<Vec<usize>, Error> {
-    let mut row_ids: Vec<usize> = Vec::new();
-    if let Some(found_idx) = index.sorted_column_values
-        .binary_search_by_key(&value, |value_in_row| value_in_row.value).ok() {
-        let mut all_matching_idx = vec![found_idx];
-        let mut current_idx = found_idx - 1;
-        while current_idx > 0 && index.sorted_column_values[current_idx].value == value {
-            all_matching_idx.push(current_idx);
-            current_idx = current_idx - 1;
+// MIN and MAX of an indexed column are read from the ends of the index instead of
+// looking at every selected row
+fn aggregate_all_rows(table: &IndexedTable, selected_row_ids: Option<Vec<usize>>, aggregates: &[(&Aggregate, Option<usize>)], options: &ExecutionOptions) -> Result<AggregatedRow, Error> {
+    let selected_row_set: Option<BTreeSet<usize>> = selected_row_ids.as_ref().map(|row_ids| row_ids.iter().cloned().collect());
+    let mut values: Vec<Option<Value>> = Vec::new();
+    let mut accumulators: Vec<Option<Accumulator>> = Vec::new();
+    for (aggregate, _) in aggregates.iter() {
+        let index = aggregate.column_name.as_ref()
+            .and_then(|column_name| table.indices.column_indices.get(column_name));
+        match index {
+            Some(index) if matches!(aggregate.function, AggregateFunction::Min | AggregateFunction::Max) => {
+                values.push(Some(aggregate_using_index(index, aggregate.function, selected_row_set.as_ref())));
+                accumulators.push(None);
+            },
+            Some(index) if aggregate.distinct => {
+                values.push(Some(aggregate_distinct_values_using_index(index, aggregate.function, selected_row_set.as_ref(), options)?));
+                accumulators.push(None);
+            },
+            _ => {
+                values.push(None);
+                accumulators.push(Some(Accumulator::of(aggregate)));
+            }
         }
-        current_idx = found_idx + 1;
-        while current_idx < index.sorted_column_values.len() && index.sorted_column_values[current_idx].value == value {
-            all_matching_idx.push(current_idx);
-            current_idx = current_idx + 1;
+    }
+    if accumulators.iter().any(|accumulator| accumulator.is_some()) {
+        let row_ids: Box<dyn Iterator<Item = usize>> = match selected_row_ids {
+            Some(selected_row_ids) => Box::new(selected_row_ids.into_iter()),
+            None => Box::new(0..table.underlying.rows.len())
+        };
+        for row_id in row_ids {
+            let row = &table.underlying.rows[row_id];
+            for (accumulator, (_, argument_position)) in accumulators.iter_mut().zip(aggregates.iter()) {
+                if let Some(accumulator) = accumulator {
+                    accumulator.add(argument_position.map(|position| &row.fields[position]), options)?;
+                }
+            }
+        }
+    }
+    let aggregate_values = values.into_iter().zip(accumulators)
+        .map(|(value, accumulator)| value.or_else(|| accumulator.map(|accumulator| accumulator.result())).unwrap_or(Value::Null))
+        .collect();
+    Ok(AggregatedRow {
+        group_key: Vec::new(),
+        aggregate_values
+    })
+}
+
+fn aggregate_using_index(index: &Index<'_>, function: AggregateFunction, selected_row_set: Option<&BTreeSet<usize>>) -> Value {
+    let values = &index.sorted_column_values[first_idx_not_null(index)..];
+    let is_selected = |value_in_row: &&ValueInRow| selected_row_set
+        .map(|selected_row_set| selected_row_set.contains(&value_in_row.row_index))
+        .unwrap_or(true);
+    let found = if function == AggregateFunction::Min {
+        values.iter().find(is_selected)
+    } else {
+        values.iter().rev().find(is_selected)
+    };
+    found.map(|value_in_row| value_in_row.value.clone()).unwrap_or(Value::Null)
+}
+
+// Equal values are adjacent in the index, so a distinct value is one differing from the preceding selected value
+fn aggregate_distinct_values_using_index(index: &Index<'_>, function: AggregateFunction, selected_row_set: Option<&BTreeSet<usize>>, options: &ExecutionOptions) -> Result<Value, Error> {
+    let mut accumulator = Accumulator::new(function);
+    let mut previous_value: Option<&Value> = None;
+    for value_in_row in index.sorted_column_values[first_idx_not_null(index)..].iter() {
+        let is_selected = selected_row_set.is_none_or(|selected_row_set| selected_row_set.contains(&value_in_row.row_index));
+        if is_selected && previous_value != Some(value_in_row.value) {
+            accumulator.add(Some(value_in_row.value), options)?;
+            previous_value = Some(value_in_row.value);
+        }
+    }
+    Ok(accumulator.result())
+}
+
+// Groups are returned in the order in which they are first encountered
+fn aggregate_groups(table: &IndexedTable, row_ids: impl Iterator<Item = usize>, group_expressions: &[Expression], aggregates: &[(&Aggregate, Option<usize>)], options: &ExecutionOptions) -> Result<Vec<AggregatedRow>, Error> {
+    let column_positions = find_column_positions(table, group_expressions.iter().flat_map(|expression| expression.column_names()).collect())?;
+    let mut group_indices: HashMap<Vec<Value>, usize> = HashMap::new();
+    let mut groups: Vec<(Vec<Value>, Vec<Accumulator>)> = Vec::new();
+    for row_id in row_ids {
+        let row = &table.underlying.rows[row_id];
+        let value_of = |reference: Reference<'_>| value_in_row(row, &column_positions, reference);
+        let group_key: Vec<Value> = group_expressions.iter()
+            .map(|expression| evaluate_expression(expression, &value_of, options))
+            .collect::<Result<Vec<Value>, Error>>()?;
+        let group_index = match group_indices.get(&group_key) {
+            Some(&group_index) => group_index,
+            None => {
+                let accumulators = aggregates.iter().map(|(aggregate, _)| Accumulator::of(aggregate)).collect();
+                groups.push((group_key.clone(), accumulators));
+                group_indices.insert(group_key, groups.len() - 1);
+                groups.len() - 1
+            }
+        };
+        for (accumulator, (_, argument_position)) in groups[group_index].1.iter_mut().zip(aggregates.iter()) {
+            accumulator.add(argument_position.map(|position| &row.fields[position]), options)?;
         }
-        all_matching_idx.iter().for_each(|&matching_idx| {
-            row_ids.push(index.sorted_column_values[matching_idx].row_index);
-        });
     }
-    Ok(row_ids)
+    Ok(groups.into_iter()
+        .map(|(group_key, accumulators)| AggregatedRow {
+            group_key,
+            aggregate_values: accumulators.into_iter().map(|accumulator| accumulator.result()).collect()
+        })
+        .collect())
+}
+
+// In an aggregation only the projected columns and aggregates exist, so the keys refer to them
+fn order_aggregated_rows(rows: &mut [ResultSetRow], projection: &[ProjectionItem], order_by: &[OrderBy]) -> Result<(), Error> {
+    let mut sort_keys: Vec<(usize, &OrderBy)> = Vec::new();
+    for key in order_by.iter() {
+        let key_name = key.expression.to_string();
+        // An aggregate only matches the same projected aggregate and a column never matches an aggregate
+        let position = projection.iter().position(|item| match (&key.expression, item.unaliased()) {
+            (Expression::Aggregate(aggregate), ProjectionItem::Aggregate(projected)) => aggregate == projected,
+            (Expression::Aggregate(_), _) => false,
+            (_, ProjectionItem::Aggregate(_)) => matches!(item, ProjectionItem::Aliased { alias, .. } if *alias == key_name),
+            (_, unaliased) => item.name() == key_name || unaliased.to_string() == key_name
+        })
+            .ok_or_else(|| anyhow!("Cannot order by {}, only the projected columns and aggregates can be used to order aggregated rows", key_name))?;
+        sort_keys.push((position, key));
+    }
+    rows.sort_by(|x, y| sort_keys.iter()
+        .map(|(position, key)| compare_for_ordering(&x.fields[*position], &y.fields[*position], key))
+        .find(|ordering| *ordering != Ordering::Equal)
+        .unwrap_or(Ordering::Equal));
+    Ok(())
+}
+
+// NULL values are ignored by all the aggregates except COUNT(*), aggregating no values
+// produces NULL (or 0 for COUNT)
+enum Accumulator {
+    Count(i64),
+    Sum(Option<Value>),
+    Min(Option<Value>),
+    Max(Option<Value>),
+    Avg { sum: f64, count: i64 },
+    Distinct { seen_values: HashSet<Value>, accumulator: Box<Accumulator> },
+    // An aggregate which failed with an arithmetic error producing NULL, such as an overflowing SUM
+    Failed
 }
 
-fn filter_by_scanning(table: &IndexedTable, filter: &Filter) -> Result<Vec<usize>, Error> {
-    let mut row_ids: Vec<usize> = Vec::new();
+impl Accumulator {
+    fn new(function: AggregateFunction) -> Accumulator {
+        match function {
+            AggregateFunction::Count => Accumulator::Count(0),
+            AggregateFunction::Sum => Accumulator::Sum(None),
+            AggregateFunction::Min => Accumulator::Min(None),
+            AggregateFunction::Max => Accumulator::Max(None),
+            AggregateFunction::Avg => Accumulator::Avg { sum: 0.0, count: 0 }
+        }
+    }
+
+    fn of(aggregate: &Aggregate) -> Accumulator {
+        if aggregate.distinct {
+            Accumulator::Distinct {
+                seen_values: HashSet::new(),
+                accumulator: Box::new(Accumulator::new(aggregate.function))
+            }
+        } else {
+            Accumulator::new(aggregate.function)
+        }
+    }
+
+    // The value is absent when the whole row is aggregated, i.e. for COUNT(*)
+    fn add(&mut self, value: Option<&Value>, options: &ExecutionOptions) -> Result<(), Error> {
+        let value = match value {
+            Some(value) if value.is_null() => return Ok(()),
+            Some(value) => value,
+            None => {
+                if let Accumulator::Count(count) = self {
+                    *count += 1;
+                }
+                return Ok(());
+            }
+        };
+        match self {
+            Accumulator::Count(count) =>
+                *count += 1,
+            Accumulator::Sum(sum) => {
+                if !value.is_numeric() {
+                    return Err(anyhow!("Cannot compute SUM of non-numeric value {}", value));
+                }
+                let added = match sum {
+                    Some(sum) => sum.add(value),
+                    None => Ok(value.clone())
+                };
+                match added {
+                    Ok(added) => *sum = Some(added),
+                    Err(error) if options.produces_null_for(&error) => *self = Accumulator::Failed,
+                    Err(error) => return Err(error)
+                }
+            },
+            Accumulator::Min(min) =>
+                if min.as_ref().is_none_or(|min| value < min) {
+                    *min = Some(value.clone());
+                },
+            Accumulator::Max(max) =>
+                if max.as_ref().is_none_or(|max| value > max) {
+                    *max = Some(value.clone());
+                },
+            Accumulator::Avg { sum, count } => {
+                *sum += value.as_float().ok_or_else(|| anyhow!("Cannot compute AVG of non-numeric value {}", value))?;
+                *count += 1;
+            },
+            Accumulator::Distinct { seen_values, accumulator } =>
+                if seen_values.insert(value.clone()) {
+                    accumulator.add(Some(value), options)?;
+                },
+            Accumulator::Failed => ()
+        }
+        Ok(())
+    }
+
+    fn result(self) -> Value {
+        match self {
+            Accumulator::Count(count) => Value::Integer(count),
+            Accumulator::Sum(sum) => sum.unwrap_or(Value::Null),
+            Accumulator::Min(min) => min.unwrap_or(Value::Null),
+            Accumulator::Max(max) => max.unwrap_or(Value::Null),
+            Accumulator::Avg { sum, count } =>
+                if count == 0 {
+                    Value::Null
+                } else {
+                    Value::Float(sum / count as f64)
+                },
+            Accumulator::Distinct { accumulator, .. } => accumulator.result(),
+            Accumulator::Failed => Value::Null
+        }
+    }
+}
+
+fn first_idx_not_null(index: &Index<'_>) -> usize {
+    index.sorted_column_values.partition_point(|value_in_row| value_in_row.value.is_null())
+}
+
+// The index is sorted by value, so every comparison corresponds to one or two contiguous
+// ranges of it, the boundaries of which are found with binary search. NULL values are
+// located at the beginning of the index and are never matched by a comparison.
+fn filter_using_index(filter: &Filter, index: &Index<'_>, row_limit: Option<usize>) -> Result<Selection, Error> {
+    let values = &index.sorted_column_values;
+    let first_idx_not_null = first_idx_not_null(index);
+    let first_idx_not_less_than = values.partition_point(|value_in_row| value_in_row.value < &filter.value);
+    let first_idx_greater_than = values.partition_point(|value_in_row| value_in_row.value <= &filter.value);
+    let matching_values: Box<dyn Iterator<Item = &ValueInRow> + '_> = match filter.filter_type {
+        FilterType::Greater => Box::new(values[first_idx_greater_than..].iter()),
+        FilterType::GreaterOrEqual => Box::new(values[first_idx_not_less_than..].iter()),
+        FilterType::Less => Box::new(values[first_idx_not_null..first_idx_not_less_than].iter()),
+        FilterType::LessOrEqual => Box::new(values[first_idx_not_null..first_idx_greater_than].iter()),
+        FilterType::Equal => Box::new(values[first_idx_not_less_than..first_idx_greater_than].iter()),
+        FilterType::NotEqual => Box::new(values[first_idx_not_null..first_idx_not_less_than].iter()
+            .chain(values[first_idx_greater_than..].iter())),
+        FilterType::Like => {
+            // Only the text values starting with the literal prefix of the pattern and the numbers, dates
+            // and intervals (matched by their text) located before all the text values can match the pattern
+            let prefix = like_pattern_prefix(&filter.value.to_string());
+            let first_idx_text = values.partition_point(|value_in_row| !matches!(value_in_row.value, Value::Text(_)));
+            let first_idx_not_less_than_prefix = values.partition_point(|value_in_row| value_in_row.value < &Value::Text(prefix.clone()));
+            let first_idx_without_prefix = first_idx_not_less_than_prefix + values[first_idx_not_less_than_prefix..].partition_point(|value_in_row|
+                matches!(value_in_row.value, Value::Text(text) if text.starts_with(&prefix))
+            );
+            Box::new(values[first_idx_not_null..first_idx_text].iter()
+                .chain(values[first_idx_not_less_than_prefix..first_idx_without_prefix].iter())
+                .filter(|value_in_row| filter.filter_type.matches(value_in_row.value, &filter.value)))
+        },
+        FilterType::ILike => Box::new(values[first_idx_not_null..].iter()
+            .filter(|value_in_row| filter.filter_type.matches(value_in_row.value, &filter.value)))
+    };
+    Ok(Selection {
+        matching: matching_values.take(row_limit.unwrap_or(usize::MAX)).map(|value_in_row| value_in_row.row_index).collect(),
+        unknown: values[..first_idx_not_null].iter().map(|value_in_row| value_in_row.row_index).collect()
+    })
+}
+
+// Every pair of bounds (sorted and not overlapping) selects a contiguous range of the index found
+// with binary search, the negation selects the non-NULL values in between the ranges
+fn select_ranges_using_index(index: &Index<'_>, bounds: Vec<(&Value, &Value)>, negated: bool, row_limit: Option<usize>) -> Selection {
+    let values = &index.sorted_column_values;
+    let first_idx_not_null = first_idx_not_null(index);
+    let ranges: Vec<(usize, usize)> = bounds.into_iter()
+        .map(|(low, high)| {
+            let first_idx_not_less_than = values.partition_point(|value_in_row| value_in_row.value < low);
+            let first_idx_greater_than = values.partition_point(|value_in_row| value_in_row.value <= high);
+            (first_idx_not_less_than, first_idx_greater_than.max(first_idx_not_less_than))
+        })
+        .collect();
+    let mut matching_slices: Vec<&[ValueInRow]> = Vec::new();
+    if negated {
+        let mut start = first_idx_not_null;
+        for (range_start, range_end) in ranges.into_iter() {
+            matching_slices.push(&values[start..range_start.max(start)]);
+            start = start.max(range_end);
+        }
+        matching_slices.push(&values[start..]);
+    } else {
+        for (range_start, range_end) in ranges.into_iter() {
+            matching_slices.push(&values[range_start..range_end]);
+        }
+    }
+    Selection {
+        matching: matching_slices.into_iter()
+            .flatten()
+            .take(row_limit.unwrap_or(usize::MAX))
+            .map(|value_in_row| value_in_row.row_index)
+            .collect(),
+        unknown: values[..first_idx_not_null].iter().map(|value_in_row| value_in_row.row_index).collect()
+    }
+}
+
+fn filter_by_scanning(table: &IndexedTable, filter: &Filter, row_limit: Option<usize>) -> Result<Selection, Error> {
+    let mut matching: Vec<usize> = Vec::new();
+    let mut unknown: BTreeSet<usize> = BTreeSet::new();
     let column_position = table.underlying.find_column_position(&filter.column_name)?;
     for (row_id, row) in table.underlying.rows.iter().enumerate() {
-        let is_row_matched_by_filter = match filter.filter_type {
-            FilterType::Greater => row.fields[column_position] > filter.value,
-            FilterType::Equal => row.fields[column_position] == filter.value
-        };
-        if is_row_matched_by_filter {
-            row_ids.push(row_id);
+        if row_limit.is_some_and(|row_limit| matching.len() >= row_limit) {
+            break;
+        }
+        match evaluate_filter(filter, &row.fields[column_position]) {
+            Some(true) => matching.push(row_id),
+            Some(false) => {},
+            None => {
+                unknown.insert(row_id);
+            }
         }
     }
-    Ok(row_ids)
+    Ok(Selection {
+        matching,
+        unknown
+    })
+}
+
+// What a value is looked up by when evaluating an expression: a column of the row or, for the
+// aggregated rows, one of the computed aggregates
+#[derive(Clone, Copy)]
+enum Reference<'a> {
+    Column(&'a str),
+    Aggregate(&'a Aggregate)
+}
+
+impl fmt::Display for Reference<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reference::Column(column_name) => write!(f, "{}", column_name),
+            Reference::Aggregate(aggregate) => write!(f, "{}", aggregate)
+        }
+    }
+}
+
+// Evaluates the predicate for a single row, the values of which are looked up by reference,
+// None stands for the unknown result of the three-valued logic
+fn evaluate_predicate<'a>(predicate: &Predicate, value_of: &dyn Fn(Reference) -> Result<&'a Value, Error>, options: &ExecutionOptions) -> Result<Option<bool>, Error> {
+    match predicate {
+        Predicate::Filter(filter) =>
+            Ok(evaluate_filter(filter, value_of(Reference::Column(&filter.column_name))?)),
+        Predicate::Comparison { left, filter_type, right } => {
+            let left = evaluate_expression(left, value_of, options)?;
+            let right = evaluate_expression(right, value_of, options)?;
+            if left.is_null() || right.is_null() {
+                Ok(None)
+            } else {
+                Ok(Some(filter_type.matches(&left, &right)))
+            }
+        },
+        Predicate::IsNull { operand, negated } =>
+            Ok(Some(evaluate_expression(operand, value_of, options)?.is_null() != *negated)),
+        Predicate::RegexMatch { left, pattern, negated } => {
+            let value = evaluate_expression(left, value_of, options)?;
+            Ok(Some(pattern.is_match(&value.to_string()) != *negated).filter(|_| !value.is_null()))
+        },
+        Predicate::Expression(expression) => {
+            let value = evaluate_expression(expression, value_of, options)?;
+            Ok(Some(value != Value::Integer(0)).filter(|_| !value.is_null()))
+        },
+        Predicate::In { operand, values, negated } => {
+            let value = evaluate_expression(operand, value_of, options)?;
+            Ok(Some(values.contains(&value) != *negated).filter(|_| !value.is_null()))
+        },
+        Predicate::Between { operand, low, high, negated } => {
+            let value = evaluate_expression(operand, value_of, options)?;
+            Ok(Some((*low <= value && value <= *high) != *negated).filter(|_| !value.is_null()))
+        },
+        Predicate::And(left, right) =>
+            match evaluate_predicate(left, value_of, options)? {
+                Some(false) => Ok(Some(false)),
+                left_result => match (left_result, evaluate_predicate(right, value_of, options)?) {
+                    (_, Some(false)) => Ok(Some(false)),
+                    (Some(true), Some(true)) => Ok(Some(true)),
+                    _ => Ok(None)
+                }
+            },
+        Predicate::Or(left, right) =>
+            match evaluate_predicate(left, value_of, options)? {
+                Some(true) => Ok(Some(true)),
+                left_result => match (left_result, evaluate_predicate(right, value_of, options)?) {
+                    (_, Some(true)) => Ok(Some(true)),
+                    (Some(false), Some(false)) => Ok(Some(false)),
+                    _ => Ok(None)
+                }
+            },
+        Predicate::Not(negated) =>
+            Ok(evaluate_predicate(negated, value_of, options)?.map(|result| !result))
+    }
+}
+
+// Evaluates the expression for a single row, the values of which are looked up by name,
+// an operation on a NULL value produces NULL
+fn evaluate_expression<'a>(expression: &Expression, value_of: &dyn Fn(Reference) -> Result<&'a Value, Error>, options: &ExecutionOptions) -> Result<Value, Error> {
+    let result = match expression {
+        Expression::Column(column_name) =>
+            Ok(value_of(Reference::Column(column_name))?.clone()),
+        Expression::Aggregate(aggregate) =>
+            Ok(value_of(Reference::Aggregate(aggregate))?.clone()),
+        Expression::Literal(value) =>
+            Ok(value.clone()),
+        Expression::Negation(negated) =>
+            match evaluate_expression(negated, value_of, options)? {
+                Value::Null => Ok(Value::Null),
+                value => value.negate()
+            },
+        Expression::Binary { operator, left, right } => {
+            let left = evaluate_expression(left, value_of, options)?;
+            let right = evaluate_expression(right, value_of, options)?;
+            if left.is_null() || right.is_null() {
+                return Ok(Value::Null);
+            }
+            match operator {
+                BinaryOperator::Add => left.add(&right),
+                BinaryOperator::Subtract => left.subtract(&right),
+                BinaryOperator::Multiply => left.multiply(&right),
+                BinaryOperator::Divide => left.divide(&right),
+                BinaryOperator::Concatenate => Ok(Value::Text(format!("{}{}", left, right)))
+            }
+        },
+        Expression::Function { function, arguments } => {
+            let mut argument_values: Vec<Value> = Vec::new();
+            for argument in arguments.iter() {
+                argument_values.push(evaluate_expression(argument, value_of, options)?);
+            }
+            function.apply(&argument_values)
+        },
+        Expression::Case { branches, otherwise } => {
+            for (condition, result) in branches.iter() {
+                if evaluate_predicate(condition, value_of, options)? == Some(true) {
+                    return evaluate_expression(result, value_of, options);
+                }
+            }
+            evaluate_otherwise(otherwise, value_of, options)
+        },
+        // NULL is not equal to any value, including NULL
+        Expression::SimpleCase { operand, branches, otherwise } => {
+            let operand = evaluate_expression(operand, value_of, options)?;
+            for (value, result) in branches.iter() {
+                if !operand.is_null() && evaluate_expression(value, value_of, options)? == operand {
+                    return evaluate_expression(result, value_of, options);
+                }
+            }
+            evaluate_otherwise(otherwise, value_of, options)
+        }
+    };
+    match result {
+        Err(error) if options.produces_null_for(&error) => Ok(Value::Null),
+        result => result
+    }
+}
+
+fn evaluate_otherwise<'a>(otherwise: &Option<Box<Expression>>, value_of: &dyn Fn(Reference) -> Result<&'a Value, Error>, options: &ExecutionOptions) -> Result<Value, Error> {
+    match otherwise {
+        Some(otherwise) => evaluate_expression(otherwise, value_of, options),
+        None => Ok(Value::Null)
+    }
+}
+
+fn evaluate_filter(filter: &Filter, value: &Value) -> Option<bool> {
+    if value.is_null() {
+        None
+    } else {
+        Some(filter.filter_type.matches(value, &filter.value))
+    }
 }
 
 #[cfg(test)]
@@ -136,7 +1486,18 @@ mod test {
     use super::*;
     use csv::ReaderBuilder;
     use std::io::Cursor;
-    use crate::table::Table;
+    use std::collections::HashMap;
+    use crate::database::NamedIndexedTable;
+    use crate::table::{Table, TableIndices};
+
+    fn indexed_database_of(table: &Table) -> IndexedDatabase<'_> {
+        IndexedDatabase {
+            tables: vec![NamedIndexedTable {
+                name: "test".to_string(),
+                table: table.build_indices().unwrap()
+            }]
+        }
+    }
 
     fn load_test_table() -> Result<Table, Error> {
         let input = r#"column1,column2,column3
@@ -153,10 +1514,11 @@ ddd,1,5
     #[test]
     fn should_execute_query_with_two_columns_in_projection_and_greater_filter() {
         let table = load_test_table().unwrap();
-        let indexed_table = table.build_indices().unwrap();
-        let query = Query::parse("PROJECT column1, column2 FILTER column1 > \"bbb\"").unwrap();
-        let result_set = execute(&query, &indexed_table).unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT column1, column2 FILTER column1 > 'bbb'").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
         assert_eq!(result_set, ResultSet {
+            columns: vec!["column1".to_string(), "column2".to_string()],
             rows: vec![
                 ResultSetRow {
                     fields: vec![Value::Text("ccc".to_string()), Value::Integer(2)]
@@ -172,79 +1534,482 @@ ddd,1,5
     }
 
     #[test]
-    fn should_execute_query_with_two_columns_in_projection_and_equal_filter() {
+    fn should_execute_query_with_two_columns_in_projection_and_equal_filter() {
+        let table = load_test_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT column1, column2 FILTER column3 = 9").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(result_set, ResultSet {
+            columns: vec!["column1".to_string(), "column2".to_string()],
+            rows: vec![
+                ResultSetRow {
+                    fields: vec![Value::Text("eee".to_string()), Value::Integer(2)]
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn should_execute_query_with_two_columns_in_projection_and_no_filter() {
+        let table = load_test_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT column1, column2").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(result_set, ResultSet {
+            columns: vec!["column1".to_string(), "column2".to_string()],
+            rows: vec![
+                ResultSetRow {
+                    fields: vec![Value::Text("bbb".to_string()), Value::Integer(3)]
+                },
+                ResultSetRow {
+                    fields: vec![Value::Text("aaa".to_string()), Value::Integer(1)]
+                },
+                ResultSetRow {
+                    fields: vec![Value::Text("ccc".to_string()), Value::Integer(2)]
+                },
+                ResultSetRow {
+                    fields: vec![Value::Text("eee".to_string()), Value::Integer(2)]
+                },
+                ResultSetRow {
+                    fields: vec![Value::Text("ddd".to_string()), Value::Integer(1)]
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn should_execute_query_with_two_columns_in_projection_and_filter_matching_no_rows() {
+        let table = load_test_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT column1, column2 FILTER column1 > 'eee'").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(result_set, ResultSet {
+            columns: vec!["column1".to_string(), "column2".to_string()],
+            rows: Vec::new()
+        })
+    }
+
+    #[test]
+    fn should_execute_query_with_single_column_in_projection() {
+        let table = load_test_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT column1 FILTER column2 > 2").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(result_set, ResultSet {
+            columns: vec!["column1".to_string()],
+            rows: vec![
+                ResultSetRow {
+                    fields: vec![Value::Text("bbb".to_string())]
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn should_produce_error_when_non_existent_column_is_used_in_projection() {
+        let table = load_test_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT column4 FILTER column2 > 2").unwrap();
+        let result = execute_on_database(&query, &database);
+        match result {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "Cannot find column column4, it does not exist in the table, existing columns column1, column2, column3"
+            ),
+            Ok(_) => panic!("Error expected"),
+        }
+    }
+
+    #[test]
+    fn should_produce_error_when_non_existent_column_is_used_in_filter() {
+        let table = load_test_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT column1 FILTER column4 > 2").unwrap();
+        let result = execute_on_database(&query, &database);
+        match result {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "Cannot find column column4, it does not exist in the table, existing columns column1, column2, column3"
+            ),
+            Ok(_) => panic!("Error expected"),
+        }
+    }
+
+    #[test]
+    fn should_find_all_rows_when_using_equal_filter() {
+        let input = r#"column1,column2
+a,1
+b,2
+c,3
+d,3
+e,3
+f,4
+"#;
+        let mut reader = ReaderBuilder::new().from_reader(Cursor::new(input));
+        let table = Table::load_from(&mut reader).unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT column1 FILTER column2 = 3").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(result_set, ResultSet {
+            columns: vec!["column1".to_string()],
+            rows: vec![
+                ResultSetRow {
+                    fields: vec![Value::Text("c".to_string())]
+                },
+                ResultSetRow {
+                    fields: vec![Value::Text("d".to_string())]
+                },
+                ResultSetRow {
+                    fields: vec![Value::Text("e".to_string())]
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn should_correctly_handle_duplicate_filter_column_values_for_greater_filter() {
+        let input = r#"column1,column2
+a,1
+b,2
+c,3
+d,3
+e,3
+f,4
+"#;
+        let mut reader = ReaderBuilder::new().from_reader(Cursor::new(input));
+        let table = Table::load_from(&mut reader).unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT column1 FILTER column2 > 3").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(result_set, ResultSet {
+            columns: vec!["column1".to_string()],
+            rows: vec![
+                ResultSetRow {
+                    fields: vec![Value::Text("f".to_string())]
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn should_execute_query_with_two_columns_in_projection_and_equal_filter_matching_no_rows() {
+        let table = load_test_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT column1, column2 FILTER column1 = 'hhh'").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(result_set, ResultSet {
+            columns: vec!["column1".to_string(), "column2".to_string()],
+            rows: Vec::new()
+        })
+    }
+
+    #[test]
+    fn should_execute_query_with_conjunction_of_filters() {
+        let table = load_test_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT column1 FILTER column2 = 2 AND column1 > 'ccc'").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(result_set, ResultSet {
+            columns: vec!["column1".to_string()],
+            rows: vec![
+                ResultSetRow {
+                    fields: vec![Value::Text("eee".to_string())]
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn should_execute_query_with_disjunction_of_filters() {
+        let table = load_test_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT column1 FILTER column2 = 3 OR column3 = 5").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(result_set, ResultSet {
+            columns: vec!["column1".to_string()],
+            rows: vec![
+                ResultSetRow {
+                    fields: vec![Value::Text("bbb".to_string())]
+                },
+                ResultSetRow {
+                    fields: vec![Value::Text("ddd".to_string())]
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn should_execute_query_with_negated_filter() {
+        let table = load_test_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT column1 FILTER NOT column2 = 2").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(result_set, ResultSet {
+            columns: vec!["column1".to_string()],
+            rows: vec![
+                ResultSetRow {
+                    fields: vec![Value::Text("bbb".to_string())]
+                },
+                ResultSetRow {
+                    fields: vec![Value::Text("aaa".to_string())]
+                },
+                ResultSetRow {
+                    fields: vec![Value::Text("ddd".to_string())]
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn should_execute_query_with_parenthesized_combination_of_filters() {
         let table = load_test_table().unwrap();
-        let indexed_table = table.build_indices().unwrap();
-        let query = Query::parse("PROJECT column1, column2 FILTER column3 = 9").unwrap();
-        let result_set = execute(&query, &indexed_table).unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT column1 FILTER (column2 = 1 OR column2 = 3) AND NOT (column1 = 'aaa')").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
         assert_eq!(result_set, ResultSet {
+            columns: vec!["column1".to_string()],
             rows: vec![
                 ResultSetRow {
-                    fields: vec![Value::Text("eee".to_string()), Value::Integer(2)]
+                    fields: vec![Value::Text("bbb".to_string())]
+                },
+                ResultSetRow {
+                    fields: vec![Value::Text("ddd".to_string())]
                 }
             ]
         })
     }
 
+    fn execute_without_indices(query: &Query, table: &Table) -> Result<ResultSet, Error> {
+        let database = IndexedDatabase {
+            tables: vec![NamedIndexedTable {
+                name: "test".to_string(),
+                table: IndexedTable {
+                    underlying: table,
+                    indices: TableIndices {
+                        column_indices: HashMap::new()
+                    }
+                }
+            }]
+        };
+        execute_on_database(query, &database)
+    }
+
+    // Runs the query made by putting every case into the template both using the indices and by
+    // scanning the table, the rows of both results are compared in their order
+    fn assert_same_results_with_and_without_index(table: &Table, query_template: &str, cases: Vec<(impl AsRef<str>, Vec<&str>)>) {
+        let database = indexed_database_of(table);
+        for (case, expected_rows) in cases.into_iter() {
+            let query_text = query_template.replace("{}", case.as_ref());
+            let query = Query::parse(&query_text).unwrap();
+            let result_using_index = execute_on_database(&query, &database).unwrap();
+            let result_by_scanning = execute_without_indices(&query, table).unwrap();
+            assert_eq!(all_column_values(&result_using_index), expected_rows, "Using index: {}", query_text);
+            assert_eq!(all_column_values(&result_by_scanning), expected_rows, "By scanning: {}", query_text);
+        }
+    }
+
+    fn first_column_values(result_set: &ResultSet) -> Vec<String> {
+        let mut values: Vec<String> = result_set.rows.iter().map(|row| row.fields[0].to_string()).collect();
+        values.sort();
+        values
+    }
+
     #[test]
-    fn should_execute_query_with_two_columns_in_projection_and_no_filter() {
+    fn should_execute_queries_with_all_comparison_operators() {
         let table = load_test_table().unwrap();
-        let indexed_table = table.build_indices().unwrap();
-        let query = Query::parse("PROJECT column1, column2").unwrap();
-        let result_set = execute(&query, &indexed_table).unwrap();
+        let expected_results = vec![
+            ("column2 > 2", vec!["bbb"]),
+            ("column2 >= 2", vec!["bbb", "ccc", "eee"]),
+            ("column2 < 2", vec!["aaa", "ddd"]),
+            ("column2 <= 2", vec!["aaa", "ccc", "ddd", "eee"]),
+            ("column2 = 2", vec!["ccc", "eee"]),
+            ("column2 != 2", vec!["aaa", "bbb", "ddd"]),
+            ("column2 <> 2", vec!["aaa", "bbb", "ddd"]),
+            ("column1 < 'ccc'", vec!["aaa", "bbb"]),
+            ("column1 >= 'ddd'", vec!["ddd", "eee"]),
+            ("column2 < 1", vec![]),
+            ("column2 > 3", vec![])
+        ];
+        assert_same_results_with_and_without_index(&table, "PROJECT column1 FILTER {} ORDER BY column1", expected_results);
+    }
+
+    #[test]
+    fn should_return_rows_in_index_order_for_less_than_filter() {
+        let table = load_test_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT column1, column2 FILTER column2 <= 2").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
         assert_eq!(result_set, ResultSet {
+            columns: vec!["column1".to_string(), "column2".to_string()],
             rows: vec![
                 ResultSetRow {
-                    fields: vec![Value::Text("bbb".to_string()), Value::Integer(3)]
+                    fields: vec![Value::Text("aaa".to_string()), Value::Integer(1)]
                 },
                 ResultSetRow {
-                    fields: vec![Value::Text("aaa".to_string()), Value::Integer(1)]
+                    fields: vec![Value::Text("ddd".to_string()), Value::Integer(1)]
                 },
                 ResultSetRow {
                     fields: vec![Value::Text("ccc".to_string()), Value::Integer(2)]
                 },
                 ResultSetRow {
                     fields: vec![Value::Text("eee".to_string()), Value::Integer(2)]
-                },
-                ResultSetRow {
-                    fields: vec![Value::Text("ddd".to_string()), Value::Integer(1)]
                 }
             ]
         })
     }
 
     #[test]
-    fn should_execute_query_with_two_columns_in_projection_and_filter_matching_no_rows() {
-        let table = load_test_table().unwrap();
-        let indexed_table = table.build_indices().unwrap();
-        let query = Query::parse("PROJECT column1, column2 FILTER column1 > \"eee\"").unwrap();
-        let result_set = execute(&query, &indexed_table).unwrap();
-        assert_eq!(result_set, ResultSet {
-            rows: Vec::new()
-        })
+    fn should_compare_integers_and_floats_numerically_in_filter() {
+        let input = r#"city,area
+a,891.8
+b,105
+c,-20.5
+d,1287
+e,99.9
+"#;
+        let mut reader = ReaderBuilder::new().from_reader(Cursor::new(input));
+        let table = Table::load_from(&mut reader).unwrap();
+        let expected_results = vec![
+            ("area > 100", vec!["a", "b", "d"]),
+            ("area > 99.95", vec!["a", "b", "d"]),
+            ("area <= 105.0", vec!["b", "c", "e"]),
+            ("area < -3", vec!["c"]),
+            ("area = 105.0", vec!["b"])
+        ];
+        assert_same_results_with_and_without_index(&table, "PROJECT city FILTER {} ORDER BY city", expected_results);
     }
 
     #[test]
-    fn should_execute_query_with_single_column_in_projection() {
-        let table = load_test_table().unwrap();
-        let indexed_table = table.build_indices().unwrap();
-        let query = Query::parse("PROJECT column1 FILTER column2 > 2").unwrap();
-        let result_set = execute(&query, &indexed_table).unwrap();
-        assert_eq!(result_set, ResultSet {
-            rows: vec![
-                ResultSetRow {
-                    fields: vec![Value::Text("bbb".to_string())]
-                }
-            ]
-        })
+    fn should_apply_three_valued_logic_to_null_values() {
+        let input = r#"name,size,kind
+a,1,x
+b,,x
+c,3,
+d,,
+e,5,y
+"#;
+        let mut reader = ReaderBuilder::new().from_reader(Cursor::new(input));
+        let table = Table::load_from(&mut reader).unwrap();
+        let expected_results = vec![
+            ("size IS NULL", vec!["b", "d"]),
+            ("size IS NOT NULL", vec!["a", "c", "e"]),
+            ("size < 4", vec!["a", "c"]),
+            ("size != 3", vec!["a", "e"]),
+            ("NOT size > 2", vec!["a"]),
+            ("NOT (size > 2 AND kind = 'x')", vec!["a", "e"]),
+            ("NOT (size > 2 OR kind = 'x')", vec![]),
+            ("size > 2 OR kind = 'x'", vec!["a", "b", "c", "e"]),
+            ("NOT NOT size = 1", vec!["a"]),
+            ("NOT (size IS NULL)", vec!["a", "c", "e"])
+        ];
+        assert_same_results_with_and_without_index(&table, "PROJECT name FILTER {} ORDER BY name", expected_results);
     }
 
     #[test]
-    fn should_produce_error_when_non_existent_column_is_used_in_projection() {
+    fn should_select_rows_with_in_list_and_between_using_index_ranges() {
+        let table = load_table_with_nulls().unwrap();
+        let database = indexed_database_of(&table);
+        let expected_results = vec![
+            ("size IN (3, 1, 3)", vec!["a", "c", "e", "f"]),
+            ("size IN (2, 4)", vec![]),
+            ("size NOT IN (1)", vec!["c", "f"]),
+            ("size NOT IN (0, 3, 5)", vec!["a", "e"]),
+            ("kind IN ('y')", vec!["d", "e"]),
+            ("kind NOT IN ('x', 'z')", vec!["d", "e"]),
+            ("size BETWEEN 1 AND 2", vec!["a", "e"]),
+            ("size BETWEEN 3 AND 1", vec![]),
+            ("size NOT BETWEEN 2 AND 5", vec!["a", "e"]),
+            ("size BETWEEN -1 AND 2", vec!["a", "e"]),
+            ("size NOT BETWEEN -5 AND 0", vec!["a", "c", "e", "f"]),
+            ("name BETWEEN 'b' AND 'd' AND size IS NULL", vec!["b", "d"]),
+            ("NOT size IN (1)", vec!["c", "f"]),
+            ("size * 2 = 2 OR name IN ('c')", vec!["a", "c", "e"])
+        ];
+        assert_same_results_with_and_without_index(&table, "PROJECT name FILTER {} ORDER BY name", expected_results);
+        let query = Query::parse("PROJECT kind, COUNT(*) GROUP BY kind HAVING COUNT(*) IN (2, 4) ORDER BY kind").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(all_column_values(&result_set), vec!["y,2"]);
+    }
+
+    #[test]
+    fn should_match_text_patterns_using_prefix_of_index() {
+        let input = r#"city,code
+Berlin,10
+Bern,
+Bergamo,24
+bergen,5
+Hamburg,20
+Ber,110
+"#;
+        let mut reader = ReaderBuilder::new().from_reader(Cursor::new(input));
+        let table = Table::load_from(&mut reader).unwrap();
+        let expected_results = vec![
+            ("city LIKE 'Ber%'", vec!["Ber", "Bergamo", "Berlin", "Bern"]),
+            ("city LIKE 'Ber%n'", vec!["Berlin", "Bern"]),
+            ("city LIKE 'Ber_'", vec!["Bern"]),
+            ("city LIKE '%burg'", vec!["Hamburg"]),
+            ("city LIKE 'Z%'", vec![]),
+            ("city ILIKE 'ber%'", vec!["Ber", "Bergamo", "Berlin", "Bern", "bergen"]),
+            ("city NOT LIKE 'Ber%'", vec!["Hamburg", "bergen"]),
+            ("code LIKE '1%'", vec!["Ber", "Berlin"]),
+            ("code NOT LIKE '%0'", vec!["Bergamo", "bergen"]),
+            ("UPPER(city) LIKE 'BERG%'", vec!["Bergamo", "bergen"])
+        ];
+        assert_same_results_with_and_without_index(&table, "PROJECT city FILTER {} ORDER BY city", expected_results);
+    }
+
+    #[test]
+    fn should_filter_by_matching_regular_expressions() {
+        let table = load_table_with_nulls().unwrap();
+        let expected_results = vec![
+            ("kind ~ 'x|y'", vec!["a", "b", "d", "e", "f"]),
+            ("kind !~ '^x$'", vec!["d", "e"]),
+            ("name || kind ~ '[ae][xy]'", vec!["a", "e"]),
+            ("size ~ '^[0-2]$' OR name ~ 'f'", vec!["a", "e", "f"]),
+            ("NOT size !~ '3'", vec!["c", "f"])
+        ];
+        assert_same_results_with_and_without_index(&table, "PROJECT name FILTER {} ORDER BY name", expected_results);
+    }
+
+    fn load_table_with_nulls() -> Result<Table, Error> {
+        let input = r#"name,size,kind
+a,1,x
+b,,x
+c,3,
+d,,y
+e,1,y
+f,3,x
+"#;
+        let mut reader = ReaderBuilder::new().from_reader(Cursor::new(input));
+        Table::load_from(&mut reader)
+    }
+
+    fn all_column_values(result_set: &ResultSet) -> Vec<String> {
+        result_set.rows.iter().map(|row| row.to_string()).collect()
+    }
+
+    #[test]
+    fn should_order_rows_by_multiple_keys() {
+        let table = load_table_with_nulls().unwrap();
+        let expected_results = vec![
+            ("ORDER BY size", vec!["b", "d", "a", "e", "c", "f"]),
+            ("ORDER BY size DESC", vec!["c", "f", "a", "e", "b", "d"]),
+            ("ORDER BY size ASC NULLS LAST", vec!["a", "e", "c", "f", "b", "d"]),
+            ("ORDER BY size DESC NULLS FIRST, name DESC", vec!["d", "b", "f", "c", "e", "a"]),
+            ("ORDER BY kind DESC, size DESC", vec!["e", "d", "f", "a", "b", "c"]),
+            ("ORDER BY kind, name DESC", vec!["c", "f", "b", "a", "e", "d"]),
+            ("FILTER kind = 'x' ORDER BY size DESC", vec!["f", "a", "b"]),
+            ("FILTER name = 'e' OR name = 'a' ORDER BY size, name DESC", vec!["e", "a"])
+        ];
+        assert_same_results_with_and_without_index(&table, "PROJECT name {}", expected_results);
+    }
+
+    #[test]
+    fn should_produce_error_when_non_existent_column_is_used_in_order_by() {
         let table = load_test_table().unwrap();
-        let indexed_table = table.build_indices().unwrap();
-        let query = Query::parse("PROJECT column4 FILTER column2 > 2").unwrap();
-        let result = execute(&query, &indexed_table);
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT column1 ORDER BY column4").unwrap();
+        let result = execute_on_database(&query, &database);
         match result {
             Err(e) => assert_eq!(
                 e.to_string(),
@@ -255,82 +2020,661 @@ ddd,1,5
     }
 
     #[test]
-    fn should_produce_error_when_non_existent_column_is_used_in_filter() {
-        let table = load_test_table().unwrap();
+    fn should_limit_number_of_returned_rows() {
+        let table = load_table_with_nulls().unwrap();
+        let expected_results = vec![
+            ("LIMIT 2", vec!["a", "b"]),
+            ("LIMIT 2 OFFSET 3", vec!["d", "e"]),
+            ("LIMIT 10 OFFSET 4", vec!["e", "f"]),
+            ("LIMIT 0", vec![]),
+            ("LIMIT 2 OFFSET 6", vec![]),
+            ("FILTER kind = 'x' LIMIT 2 OFFSET 1", vec!["b", "f"]),
+            ("ORDER BY size DESC LIMIT 3", vec!["c", "f", "a"]),
+            ("ORDER BY size DESC, name DESC LIMIT 3 OFFSET 1", vec!["c", "e", "a"]),
+            ("ORDER BY kind LIMIT 2 OFFSET 1", vec!["a", "b"]),
+            ("FILTER name = 'e' OR name = 'a' OR name = 'c' ORDER BY size DESC LIMIT 2", vec!["c", "a"]),
+            ("ORDER BY size * 2 DESC, name LIMIT 100000000000000", vec!["c", "f", "a", "e", "b", "d"]),
+            ("LIMIT 18446744073709551615 OFFSET 5", vec!["f"]),
+            ("ORDER BY name DESC LIMIT 18446744073709551615 OFFSET 4", vec!["b", "a"]),
+            ("FILTER size + 0 > 0 LIMIT 2 OFFSET 1", vec!["c", "e"]),
+            ("FILTER kind IN ('x') LIMIT 2", vec!["a", "b"])
+        ];
+        assert_same_results_with_and_without_index(&table, "PROJECT name {}", expected_results);
+        // The scan stops at the limit, before the division by zero in the row "c"
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT name FILTER 6 / (size - 3) < 0 LIMIT 1").unwrap();
+        assert_eq!(all_column_values(&execute_on_database(&query, &database).unwrap()), vec!["a"]);
+        let query = Query::parse("PROJECT name FILTER 6 / (size - 3) < 0 LIMIT 2").unwrap();
+        assert!(execute_on_database(&query, &database).is_err());
+    }
+
+    #[test]
+    fn should_select_only_rows_up_to_limit_using_index() {
+        let table = load_table_with_nulls().unwrap();
         let indexed_table = table.build_indices().unwrap();
-        let query = Query::parse("PROJECT column1 FILTER column4 > 2").unwrap();
-        let result = execute(&query, &indexed_table);
+        let expected_results = vec![
+            ("size > 0", vec![0, 4]),
+            ("kind BETWEEN 'x' AND 'y'", vec![0, 1]),
+            ("size IN (3, 1)", vec![0, 4]),
+            ("name NOT IN ('a')", vec![1, 2]),
+            ("name LIKE '%'", vec![0, 1]),
+            ("size IS NULL", vec![1, 3]),
+            ("size > 0 OR kind = 'y'", vec![0, 2]),
+            ("NOT size = 3 AND kind = 'y'", vec![4])
+        ];
+        for (condition, expected_row_ids) in expected_results.into_iter() {
+            let query = Query::parse(&format!("PROJECT name FILTER {}", condition)).unwrap();
+            let selection = select_rows(&indexed_table, query.filter.as_ref().unwrap(), Some(2), &ExecutionOptions::default()).unwrap();
+            assert_eq!(selection.matching, expected_row_ids, "{}", condition);
+        }
+        let query = Query::parse("PROJECT name FILTER size > 0 LIMIT 1 OFFSET 1").unwrap();
+        assert_eq!(all_column_values(&execute_on_database(&query, &indexed_database_of(&table)).unwrap()), vec!["e"]);
+    }
+
+    fn load_events_table() -> Result<Table, Error> {
+        let input = r#"name,day,started
+launch,2024-01-15,2024-01-15T09:30:00
+review,2024-02-29,2024-03-01 17:45:00
+retro,2024-03-31,2024-03-31T23:59:59
+kickoff,2023-12-31,
+planning,,2024-01-02 08:00:00
+"#;
+        let mut reader = csv::Reader::from_reader(input.as_bytes());
+        Table::load_from(&mut reader)
+    }
+
+    fn load_cities_table() -> Result<Table, Error> {
+        let input = r#"city,country,population,area
+Berlin,Germany,3644826,891.8
+Hamburg,Germany,1841179,755.2
+Munich,Germany,1471508,
+Madrid,Spain,3266126,604.3
+Barcelona,Spain,1620343,101.9
+Rome,Italy,2873000,1287.4
+"#;
+        let mut reader = ReaderBuilder::new().from_reader(Cursor::new(input));
+        Table::load_from(&mut reader)
+    }
+
+    #[test]
+    fn should_evaluate_case_expressions_in_all_clauses() {
+        let table = load_cities_table().unwrap();
+        let database = indexed_database_of(&table);
+        let size = "CASE WHEN population > 3000000 THEN 'large' WHEN population > 1500000 THEN 'medium' ELSE 'small' END";
+        let expected_results = vec![
+            (format!("PROJECT city, {} AS size ORDER BY city", size),
+                vec!["Barcelona,medium", "Berlin,large", "Hamburg,medium", "Madrid,large", "Munich,small", "Rome,medium"]),
+            (format!("PROJECT {} AS size, COUNT(*) GROUP BY size ORDER BY COUNT(*) DESC", size),
+                vec!["medium,3", "large,2", "small,1"]),
+            ("PROJECT CASE country WHEN 'Germany' THEN 'DE' ELSE 'other' END, SUM(population) GROUP BY CASE country WHEN 'Germany' THEN 'DE' ELSE 'other' END".to_string(),
+                vec!["DE,6957513", "other,7759469"]),
+            ("PROJECT city FILTER CASE WHEN area IS NULL THEN 0 ELSE area END < 700 ORDER BY city".to_string(),
+                vec!["Barcelona", "Madrid", "Munich"]),
+            ("PROJECT city ORDER BY CASE country WHEN 'Spain' THEN 1 WHEN 'Italy' THEN 2 ELSE 3 END, city".to_string(),
+                vec!["Barcelona", "Madrid", "Rome", "Berlin", "Hamburg", "Munich"]),
+            ("PROJECT city ORDER BY CASE country WHEN 'Spain' THEN 1 WHEN 'Italy' THEN 2 ELSE 3 END DESC, city LIMIT 2".to_string(),
+                vec!["Berlin", "Hamburg"]),
+            ("PROJECT city, CASE area WHEN NULL THEN 'none' ELSE 'some' END FILTER country = 'Germany'".to_string(),
+                vec!["Berlin,some", "Hamburg,some", "Munich,some"]),
+            ("PROJECT city, population / 1000000 AS millions ORDER BY millions DESC, city LIMIT 3".to_string(),
+                vec!["Berlin,3", "Madrid,3", "Rome,2"])
+        ];
+        assert_same_results_with_and_without_index(&table, "{}", expected_results);
+        let query = Query::parse("PROJECT CASE country WHEN 'Germany' THEN 'DE' END, COUNT(*) GROUP BY CASE country WHEN 'Germany' THEN 'DE' END").unwrap();
+        assert_eq!(execute_on_database(&query, &database).unwrap().columns, vec!["CASE country WHEN 'Germany' THEN 'DE' END", "COUNT(*)"]);
+    }
+
+    #[test]
+    fn should_aggregate_rows_by_group() {
+        let table = load_cities_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT country, COUNT(*), COUNT(area), SUM(population), MIN(city), MAX(area), AVG(population) GROUP BY country").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(all_column_values(&result_set), vec![
+            "Germany,3,2,6957513,Berlin,891.8,2319171",
+            "Spain,2,2,4886469,Barcelona,604.3,2443234.5",
+            "Italy,1,1,2873000,Rome,1287.4,2873000"
+        ]);
+    }
+
+    #[test]
+    fn should_order_and_limit_aggregated_rows() {
+        let table = load_cities_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT country, COUNT(*) FILTER population > 1500000 GROUP BY country ORDER BY COUNT(*) DESC, country LIMIT 2").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(all_column_values(&result_set), vec!["Germany,2", "Spain,2"]);
+    }
+
+    #[test]
+    fn should_compute_global_aggregates() {
+        let table = load_cities_table().unwrap();
+        let expected_results = vec![
+            ("PROJECT COUNT(*), MIN(area), MAX(area), SUM(population)", vec!["6,101.9,1287.4,14716982"]),
+            ("PROJECT MIN(population), MAX(city) FILTER country = 'Germany'", vec!["1471508,Munich"]),
+            ("PROJECT MIN(area), MAX(area) FILTER country = 'Germany' AND population < 2000000", vec!["755.2,755.2"]),
+            ("PROJECT COUNT(*), MIN(area), SUM(population), AVG(area) FILTER population > 5000000", vec!["0,NULL,NULL,NULL"])
+        ];
+        assert_same_results_with_and_without_index(&table, "{}", expected_results);
+    }
+
+    #[test]
+    fn should_project_distinct_values_and_count_them() {
+        let table = load_table_with_nulls().unwrap();
+        let database = indexed_database_of(&table);
+        let expected_results = vec![
+            ("PROJECT DISTINCT kind ORDER BY kind", vec!["NULL", "x", "y"]),
+            ("PROJECT DISTINCT size AS s ORDER BY size DESC NULLS LAST LIMIT 2", vec!["3", "1"]),
+            ("PROJECT DISTINCT kind FILTER size IS NOT NULL ORDER BY kind", vec!["NULL", "x", "y"]),
+            ("PROJECT DISTINCT kind FILTER name > 'c' ORDER BY kind LIMIT 5 OFFSET 1", vec!["y"]),
+            ("PROJECT DISTINCT size, kind ORDER BY name", vec!["1,x", "NULL,x", "3,NULL", "NULL,y", "1,y", "3,x"]),
+            ("PROJECT DISTINCT size * 0 AS zero ORDER BY name", vec!["0", "NULL"]),
+            ("PROJECT COUNT(DISTINCT size), COUNT(size), SUM(DISTINCT size), AVG(DISTINCT size)", vec!["2,4,4,2"]),
+            ("PROJECT COUNT(DISTINCT kind) FILTER name != 'a'", vec!["2"]),
+            ("PROJECT kind, COUNT(DISTINCT size) GROUP BY kind ORDER BY kind", vec!["NULL,1", "x,2", "y,1"]),
+            ("PROJECT DISTINCT COUNT(*) GROUP BY kind", vec!["3", "1", "2"])
+        ];
+        assert_same_results_with_and_without_index(&table, "{}", expected_results);
+        let query = Query::parse("PROJECT DISTINCT size AS s").unwrap();
+        assert_eq!(execute_on_database(&query, &database).unwrap().columns, vec!["s"]);
+    }
+
+    #[test]
+    fn should_produce_error_when_projected_column_is_not_grouped() {
+        let table = load_cities_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT city, COUNT(*) GROUP BY country").unwrap();
+        let result = execute_on_database(&query, &database);
         match result {
-            Err(e) => assert_eq!(
-                e.to_string(),
-                "Cannot find column column4, it does not exist in the table, existing columns column1, column2, column3"
-            ),
+            Err(e) => assert_eq!(e.to_string(), "Column city must appear in GROUP BY or be used in an aggregate"),
             Ok(_) => panic!("Error expected"),
         }
     }
 
     #[test]
-    fn should_find_all_rows_when_using_equal_filter() {
-        let input = r#"column1,column2
-a,1
-b,2
-c,3
-d,3
-e,3
-f,4
+    fn should_produce_error_when_summing_text_values() {
+        let table = load_cities_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT SUM(city)").unwrap();
+        let result = execute_on_database(&query, &database);
+        match result {
+            Err(e) => assert_eq!(e.to_string(), "Cannot compute SUM of non-numeric value Berlin"),
+            Ok(_) => panic!("Error expected"),
+        }
+    }
+
+    #[test]
+    fn should_filter_aggregated_rows_with_having() {
+        let table = load_cities_table().unwrap();
+        let database = indexed_database_of(&table);
+        let expected_results = vec![
+            ("PROJECT country, COUNT(*) GROUP BY country HAVING COUNT(*) > 1", vec!["Germany,3", "Spain,2"]),
+            ("PROJECT country, MAX(area) GROUP BY country HAVING MAX(area) < 1000 AND NOT country = 'Spain'", vec!["Germany,891.8"]),
+            ("PROJECT country, COUNT(*) GROUP BY country HAVING COUNT(*) = 1 OR country = 'Spain' ORDER BY COUNT(*)", vec!["Italy,1", "Spain,2"]),
+            ("PROJECT COUNT(*) GROUP BY country HAVING country >= 'Italy'", vec!["2", "1"]),
+            ("PROJECT COUNT(*) HAVING COUNT(*) > 10", vec![])
+        ];
+        for (query, expected_values) in expected_results.into_iter() {
+            let query = Query::parse(query).unwrap();
+            let result_set = execute_on_database(&query, &database).unwrap();
+            assert_eq!(all_column_values(&result_set), expected_values);
+        }
+    }
+
+    #[test]
+    fn should_treat_unknown_having_result_as_not_matching() {
+        let table = load_cities_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT country, MIN(area) FILTER area IS NULL GROUP BY country HAVING MIN(area) < 100 OR MIN(area) IS NOT NULL").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(all_column_values(&result_set), Vec::<String>::new());
+    }
+
+    #[test]
+    fn should_distinguish_aggregates_from_columns_named_like_them() {
+        let input = r#"k,SUM(v),v
+a,10,1
+a,10,2
+b,1,5
+"#;
+        let mut reader = csv::Reader::from_reader(input.as_bytes());
+        let table = Table::load_from(&mut reader).unwrap();
+        let database = indexed_database_of(&table);
+        let expected_results = vec![
+            ("PROJECT k, \"SUM(v)\", SUM(v) GROUP BY k, \"SUM(v)\" HAVING \"SUM(v)\" > 5", vec!["a,10,3"]),
+            ("PROJECT k, \"SUM(v)\", SUM(v) GROUP BY k, \"SUM(v)\" HAVING SUM(v) > 4", vec!["b,1,5"]),
+            ("PROJECT k, \"SUM(v)\", SUM(v) GROUP BY k, \"SUM(v)\" ORDER BY \"SUM(v)\"", vec!["b,1,5", "a,10,3"]),
+            ("PROJECT k, \"SUM(v)\", SUM(v) GROUP BY k, \"SUM(v)\" ORDER BY SUM(v)", vec!["a,10,3", "b,1,5"]),
+            ("PROJECT k, SUM(v) GROUP BY k HAVING SUM(v) IN (3, 4) OR SUM(v) BETWEEN 5 AND 6", vec!["a,3", "b,5"]),
+            ("PROJECT k, v FILTER \"SUM(v)\" = 10", vec!["a,1", "a,2"])
+        ];
+        for (query, expected_rows) in expected_results.into_iter() {
+            let result_set = execute_on_database(&Query::parse(query).unwrap(), &database).unwrap();
+            assert_eq!(all_column_values(&result_set), expected_rows, "{}", query);
+        }
+        let query = Query::parse("PROJECT k, SUM(v) GROUP BY k ORDER BY \"SUM(v)\"").unwrap();
+        match execute_on_database(&query, &database) {
+            Err(e) => assert_eq!(e.to_string(), "Cannot order by SUM(v), only the projected columns and aggregates can be used to order aggregated rows"),
+            Ok(_) => panic!("Error expected")
+        }
+    }
+
+    #[test]
+    fn should_execute_query_against_table_given_in_from() {
+        let cities = load_cities_table().unwrap();
+        let other = load_test_table().unwrap();
+        let mut database = indexed_database_of(&other);
+        database.tables.push(NamedIndexedTable {
+            name: "cities".to_string(),
+            table: cities.build_indices().unwrap()
+        });
+        let query = Query::parse("PROJECT city FROM cities FILTER country = 'Spain'").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(all_column_values(&result_set), vec!["Madrid", "Barcelona"]);
+        let query = Query::parse("PROJECT column1 FROM test FILTER column2 = 3").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(all_column_values(&result_set), vec!["bbb"]);
+        let query = Query::parse("PROJECT city FILTER country = 'Spain'").unwrap();
+        match execute_on_database(&query, &database) {
+            Err(e) => assert_eq!(e.to_string(), "Table to query has to be specified with FROM, existing tables test, cities"),
+            Ok(_) => panic!("Error expected")
+        }
+    }
+
+    #[test]
+    fn should_execute_query_against_single_indexed_table() {
+        let cities = load_cities_table().unwrap();
+        let indexed_cities = cities.build_indices().unwrap();
+        let expected_results = vec![
+            ("PROJECT city FILTER country = 'Spain'", vec!["Barcelona", "Madrid"]),
+            ("PROJECT cities.city FROM cities FILTER cities.population > 3000000", vec!["Berlin", "Madrid"])
+        ];
+        for (query, expected_values) in expected_results.into_iter() {
+            let result_set = execute(&Query::parse(query).unwrap(), &indexed_cities).unwrap();
+            assert_eq!(first_column_values(&result_set), expected_values, "{}", query);
+        }
+        let query = Query::parse("PROJECT city FROM cities JOIN countries ON country = name").unwrap();
+        match execute(&query, &indexed_cities) {
+            Err(e) => assert_eq!(e.to_string(), "Cannot join tables when querying a single table, the tables have to be added to a database"),
+            Ok(_) => panic!("Error expected")
+        }
+    }
+
+    fn load_countries_table() -> Result<Table, Error> {
+        let input = r#"country,capital,currency
+Spain,Madrid,EUR
+Germany,Berlin,EUR
+Sweden,Stockholm,SEK
+"#;
+        let mut reader = ReaderBuilder::new().from_reader(Cursor::new(input));
+        Table::load_from(&mut reader)
+    }
+
+    fn database_of<'a>(tables: Vec<(&str, &'a Table)>, with_indices: bool) -> IndexedDatabase<'a> {
+        IndexedDatabase {
+            tables: tables.into_iter()
+                .map(|(name, table)| NamedIndexedTable {
+                    name: name.to_string(),
+                    table: if with_indices {
+                        table.build_indices().unwrap()
+                    } else {
+                        IndexedTable {
+                            underlying: table,
+                            indices: TableIndices {
+                                column_indices: HashMap::new()
+                            }
+                        }
+                    }
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn should_join_tables() {
+        let cities = load_cities_table().unwrap();
+        let countries = load_countries_table().unwrap();
+        let expected_results = vec![
+            ("PROJECT city, capital FROM cities JOIN countries ON cities.country = countries.country",
+                vec!["Barcelona,Madrid", "Berlin,Berlin", "Hamburg,Berlin", "Madrid,Madrid", "Munich,Berlin"]),
+            ("PROJECT city, currency FROM cities LEFT OUTER JOIN countries ON countries.country = cities.country",
+                vec!["Barcelona,EUR", "Berlin,EUR", "Hamburg,EUR", "Madrid,EUR", "Munich,EUR", "Rome,NULL"]),
+            ("PROJECT capital, city FROM countries LEFT JOIN cities ON countries.country = cities.country FILTER city IS NULL",
+                vec!["Stockholm,NULL"]),
+            ("PROJECT city FROM cities INNER JOIN countries ON cities.country = countries.country FILTER population > 2000000 AND capital = 'Berlin'",
+                vec!["Berlin"]),
+            ("PROJECT city FROM cities JOIN countries ON cities.country = countries.country FILTER city = 'Rome' OR currency = 'SEK'",
+                vec![]),
+            ("PROJECT countries.country, COUNT(*) FROM cities LEFT JOIN countries ON cities.country = countries.country GROUP BY countries.country",
+                vec!["Germany,3", "NULL,1", "Spain,2"])
+        ];
+        for (query, expected_values) in expected_results.into_iter() {
+            let query = Query::parse(query).unwrap();
+            for with_indices in [true, false] {
+                let database = database_of(vec![("cities", &cities), ("countries", &countries)], with_indices);
+                let mut values = all_column_values(&execute_on_database(&query, &database).unwrap());
+                values.sort();
+                assert_eq!(values, expected_values, "With indices: {}, query: {:?}", with_indices, query);
+            }
+        }
+    }
+
+    #[test]
+    fn should_join_few_filtered_rows_using_index_of_joined_table() {
+        let cities = load_cities_table().unwrap();
+        let countries = load_countries_table().unwrap();
+        let database = database_of(vec![("cities", &cities), ("countries", &countries)], true);
+        let query = Query::parse("PROJECT city, capital FROM cities LEFT JOIN countries ON cities.country = countries.country FILTER city = 'Rome' OR city = 'Madrid'").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(all_column_values(&result_set), vec!["Madrid,Madrid", "Rome,NULL"]);
+    }
+
+    #[test]
+    fn should_produce_error_when_joined_column_name_is_ambiguous() {
+        let cities = load_cities_table().unwrap();
+        let countries = load_countries_table().unwrap();
+        let database = database_of(vec![("cities", &cities), ("countries", &countries)], true);
+        let expected_errors = vec![
+            ("PROJECT country FROM cities JOIN countries ON cities.country = countries.country",
+                "Column country is ambiguous, it exists in the tables cities, countries"),
+            ("PROJECT language FROM cities JOIN countries ON cities.country = countries.country",
+                "Cannot find column language, it does not exist in the tables cities, countries"),
+            ("PROJECT city FROM cities JOIN countries ON cities.city = cities.country",
+                "Cannot join table countries on cities.city = cities.country, a column of it has to be compared with a column of a preceding table"),
+            ("PROJECT city FROM cities JOIN cities ON city = city",
+                "Table cities is used more than once in the query")
+        ];
+        for (query, expected_error) in expected_errors.into_iter() {
+            let query = Query::parse(query).unwrap();
+            match execute_on_database(&query, &database) {
+                Err(e) => assert_eq!(e.to_string(), expected_error),
+                Ok(_) => panic!("Error expected")
+            }
+        }
+    }
+
+    #[test]
+    fn should_allow_column_names_qualified_with_queried_table_name() {
+        let cities = load_cities_table().unwrap();
+        let database = database_of(vec![("cities", &cities)], true);
+        let query = Query::parse("PROJECT cities.city, MAX(cities.area) FROM cities FILTER cities.country = 'Spain' GROUP BY cities.city ORDER BY cities.city").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(all_column_values(&result_set), vec!["Barcelona,101.9", "Madrid,604.3"]);
+    }
+
+    #[test]
+    fn should_query_columns_with_quoted_names() {
+        let input = r#"city,Area (km2),FILTER
+Berlin,891.8,a
+Madrid,604.3,b
 "#;
         let mut reader = ReaderBuilder::new().from_reader(Cursor::new(input));
         let table = Table::load_from(&mut reader).unwrap();
-        let indexed_table = table.build_indices().unwrap();
-        let query = Query::parse("PROJECT column1 FILTER column2 = 3").unwrap();
-        let result_set = execute(&query, &indexed_table).unwrap();
-        assert_eq!(result_set, ResultSet {
-            rows: vec![
-                ResultSetRow {
-                    fields: vec![Value::Text("d".to_string())]
-                },
-                ResultSetRow {
-                    fields: vec![Value::Text("c".to_string())]
-                },
-                ResultSetRow {
-                    fields: vec![Value::Text("e".to_string())]
-                }
-            ]
-        })
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT city, `Area (km2)` FILTER \"Area (km2)\" > 700 OR \"FILTER\" = 'b' ORDER BY \"Area (km2)\"").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(all_column_values(&result_set), vec!["Madrid,604.3", "Berlin,891.8"]);
     }
 
     #[test]
-    fn should_correctly_handle_duplicate_filter_column_values_for_greater_filter() {
-        let input = r#"column1,column2
+    fn should_project_all_columns_and_columns_matching_pattern() {
+        let table = load_cities_table().unwrap();
+        let database = indexed_database_of(&table);
+        let expected_results = vec![
+            ("PROJECT * FILTER city = 'Rome'", vec!["city", "country", "population", "area"], vec!["Rome,Italy,2873000,1287.4"]),
+            ("PROJECT * EXCEPT population, test.area FROM test FILTER country = 'Spain'", vec!["city", "country"], vec!["Madrid,Spain", "Barcelona,Spain"]),
+            ("PROJECT area, COLUMNS('c.*') FILTER area > 1000", vec!["area", "city", "country"], vec!["1287.4,Rome,Italy"]),
+            ("PROJECT COLUMNS('ity') LIMIT 1", vec![], vec![""])
+        ];
+        for (query, expected_columns, expected_values) in expected_results.into_iter() {
+            let result_set = execute_on_database(&Query::parse(query).unwrap(), &database).unwrap();
+            assert_eq!(result_set.columns, expected_columns, "{}", query);
+            assert_eq!(all_column_values(&result_set), expected_values, "{}", query);
+        }
+    }
+
+    #[test]
+    fn should_expand_all_columns_of_joined_tables() {
+        let cities = load_cities_table().unwrap();
+        let countries = load_countries_table().unwrap();
+        let database = database_of(vec![("cities", &cities), ("countries", &countries)], true);
+        let query = Query::parse("PROJECT * EXCEPT population, area, countries.country FROM cities JOIN countries ON cities.country = countries.country FILTER city = 'Berlin'").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(result_set.columns, vec!["cities.city", "cities.country", "countries.capital", "countries.currency"]);
+        assert_eq!(all_column_values(&result_set), vec!["Berlin,Germany,Berlin,EUR"]);
+        let query = Query::parse("PROJECT city, COLUMNS('countries[.].*') FROM cities JOIN countries ON cities.country = countries.country FILTER city = 'Berlin'").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(result_set.columns, vec!["cities.city", "countries.country", "countries.capital", "countries.currency"]);
+        assert_eq!(all_column_values(&result_set), vec!["Berlin,Germany,Berlin,EUR"]);
+    }
+
+    #[test]
+    fn should_produce_error_when_all_columns_are_projected_in_aggregation() {
+        let table = load_cities_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT COUNT(*), * EXCEPT city GROUP BY country").unwrap();
+        match execute_on_database(&query, &database) {
+            Err(e) => assert_eq!(e.to_string(), "Cannot project * EXCEPT city when aggregating the rows, only the grouped columns and aggregates can be projected"),
+            Ok(_) => panic!("Error expected")
+        }
+    }
+
+    #[test]
+    fn should_project_computed_expressions_with_aliases() {
+        let table = load_cities_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT city || ' (' || country || ')' AS name, population / 1000 AS thousands, area * 2, 1 AS one FILTER population < 2000000 ORDER BY city").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(result_set.columns, vec!["name", "thousands", "area * 2", "one"]);
+        assert_eq!(all_column_values(&result_set), vec![
+            "Barcelona (Spain),1620,203.8,1",
+            "Hamburg (Germany),1841,1510.4,1",
+            "Munich (Germany),1471,NULL,1"
+        ]);
+    }
+
+    #[test]
+    fn should_compute_expressions_of_grouped_columns() {
+        let table = load_cities_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT country || '!' AS exclaimed, COUNT(*) AS cities GROUP BY country ORDER BY cities DESC, exclaimed").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(result_set.columns, vec!["exclaimed", "cities"]);
+        assert_eq!(all_column_values(&result_set), vec!["Germany!,3", "Spain!,2", "Italy!,1"]);
+        let query = Query::parse("PROJECT city || '!', COUNT(*) GROUP BY country").unwrap();
+        match execute_on_database(&query, &database) {
+            Err(e) => assert_eq!(e.to_string(), "Column city must appear in GROUP BY or be used in an aggregate"),
+            Ok(_) => panic!("Error expected")
+        }
+    }
+
+    #[test]
+    fn should_produce_error_when_dividing_by_zero() {
+        let table = load_cities_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT population / (area - area) FILTER city = 'Berlin'").unwrap();
+        match execute_on_database(&query, &database) {
+            Err(e) => assert_eq!(e.to_string(), "Division by zero when dividing 3644826 by 0"),
+            Ok(_) => panic!("Error expected")
+        }
+    }
+
+    #[test]
+    fn should_produce_null_for_arithmetic_errors_when_configured() {
+        let table = load_cities_table().unwrap();
+        let database = indexed_database_of(&table);
+        let options = ExecutionOptions { arithmetic_errors: ArithmeticErrors::Null };
+        let query = Query::parse("PROJECT city, population / (area - area), population * 9223372036854, ROUND(LN(area - 891.8), 2), MOD(population, 0) \
+            FILTER city = 'Berlin' OR city = 'Rome' ORDER BY city").unwrap();
+        let result_set = execute_on_database_with_options(&query, &database, &options).unwrap();
+        assert_eq!(all_column_values(&result_set), vec!["Berlin,NULL,NULL,NULL,NULL", "Rome,NULL,NULL,5.98,NULL"]);
+        let query = Query::parse("PROJECT city FILTER population / (area - area) > 1 OR CASE WHEN MOD(population, 0) = 0 THEN 1 ELSE 2 END = 1 ORDER BY city").unwrap();
+        assert_eq!(first_column_values(&execute_on_database_with_options(&query, &database, &options).unwrap()), Vec::<String>::new());
+        let query = Query::parse("PROJECT LN(area - 891.8) FILTER city = 'Berlin'").unwrap();
+        match execute_on_database(&query, &database) {
+            Err(e) => assert_eq!(e.to_string(), "Cannot take the logarithm of 0"),
+            Ok(_) => panic!("Error expected")
+        }
+        let input = r#"k,v
+a,9223372036854775807
 a,1
 b,2
-c,3
-d,3
-e,3
-f,4
+b,3
 "#;
-        let mut reader = ReaderBuilder::new().from_reader(Cursor::new(input));
+        let mut reader = csv::Reader::from_reader(input.as_bytes());
         let table = Table::load_from(&mut reader).unwrap();
-        let indexed_table = table.build_indices().unwrap();
-        let query = Query::parse("PROJECT column1 FILTER column2 > 3").unwrap();
-        let result_set = execute(&query, &indexed_table).unwrap();
-        assert_eq!(result_set, ResultSet {
-            rows: vec![
-                ResultSetRow {
-                    fields: vec![Value::Text("f".to_string())]
-                }
-            ]
-        })
+        let database = indexed_database_of(&table);
+        let expected_results = vec![
+            ("PROJECT k, SUM(v), COUNT(*) GROUP BY k", vec!["a,NULL,2", "b,5,2"]),
+            ("PROJECT SUM(v), SUM(DISTINCT v), MAX(v)", vec!["NULL,NULL,9223372036854775807"])
+        ];
+        for (query, expected_rows) in expected_results.into_iter() {
+            let query = Query::parse(query).unwrap();
+            assert_eq!(all_column_values(&execute_on_database_with_options(&query, &database, &options).unwrap()), expected_rows);
+            match execute_on_database(&query, &database) {
+                Err(e) => assert!(e.is::<ArithmeticError>(), "{}", e),
+                Ok(_) => panic!("Error expected")
+            }
+        }
     }
 
     #[test]
-    fn should_execute_query_with_two_columns_in_projection_and_equal_filter_matching_no_rows() {
-        let table = load_test_table().unwrap();
-        let indexed_table = table.build_indices().unwrap();
-        let query = Query::parse("PROJECT column1, column2 FILTER column1 = \"hhh\"").unwrap();
-        let result_set = execute(&query, &indexed_table).unwrap();
-        assert_eq!(result_set, ResultSet {
-            rows: Vec::new()
-        })
+    fn should_apply_numeric_functions() {
+        let table = load_cities_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT city, ROUND(population / area), ROUND(area, -2), FLOOR(area), CEIL(area), ABS(-population), \
+            POWER(MOD(population, 10), 2) FILTER country = 'Germany' ORDER BY city").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(all_column_values(&result_set), vec![
+            "Berlin,4087,900,891,892,3644826,36",
+            "Hamburg,2438,800,755,756,1841179,81",
+            "Munich,NULL,NULL,NULL,NULL,1471508,64"
+        ]);
+        let expected_results = vec![
+            ("ROUND(population, -6) = 2000000", vec!["Barcelona", "Hamburg"]),
+            ("MOD(population, 2) = 0 AND ABS(area - 700) < 200", vec!["Berlin", "Madrid"]),
+            ("LN(population) > 14.9", vec!["Berlin", "Madrid"])
+        ];
+        assert_same_results_with_and_without_index(&table, "PROJECT city FILTER {} ORDER BY city", expected_results);
+        let query = Query::parse("PROJECT ROUND(city) FILTER city = 'Nowhere'").unwrap();
+        match execute_on_database(&query, &database) {
+            Err(e) => assert_eq!(e.to_string(), "Function ROUND expects numeric as argument 1, found text"),
+            Ok(_) => panic!("Error expected")
+        }
+    }
+
+    #[test]
+    fn should_filter_and_compute_dates_and_timestamps() {
+        let table = load_events_table().unwrap();
+        let database = indexed_database_of(&table);
+        let expected_results = vec![
+            ("day >= DATE '2024-02-29'", vec!["retro", "review"]),
+            ("day BETWEEN DATE '2024-01-01' AND DATE '2024-02-29'", vec!["launch", "review"]),
+            ("started < TIMESTAMP '2024-01-15 09:30:01'", vec!["launch", "planning"]),
+            ("started > day", vec!["launch", "retro", "review"]),
+            ("day IN (DATE '2023-12-31', DATE '2024-03-31')", vec!["kickoff", "retro"]),
+            ("day + INTERVAL '1 month' = DATE '2024-04-30' OR day - 1 = DATE '2024-02-28'", vec!["retro", "review"]),
+            ("started - INTERVAL '12 hours' <= TIMESTAMP '2024-01-01 20:00'", vec!["planning"]),
+            ("EXTRACT(DOW FROM day) = 0", vec!["kickoff", "retro"])
+        ];
+        assert_same_results_with_and_without_index(&table, "PROJECT name FILTER {} ORDER BY name", expected_results);
+        let query = Query::parse("PROJECT name, day + INTERVAL '1 month', started - day, day - DATE '2024-01-01', \
+            DATE_DIFF('month', day, started), EXTRACT(HOUR FROM started) FILTER day IS NOT NULL ORDER BY day").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(all_column_values(&result_set), vec![
+            "kickoff,2024-01-31,NULL,-1,NULL,NULL",
+            "launch,2024-02-15,09:30:00,14,0,9",
+            "review,2024-03-29,1 day 17:45:00,59,0,17",
+            "retro,2024-04-30,23:59:59,90,0,23"
+        ]);
+        let query = Query::parse("PROJECT DATE_TRUNC('month', day) AS month, COUNT(*), MAX(started) GROUP BY month ORDER BY month").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(all_column_values(&result_set), vec![
+            "NULL,1,2024-01-02 08:00:00",
+            "2023-12-01,1,NULL",
+            "2024-01-01,1,2024-01-15 09:30:00",
+            "2024-02-01,1,2024-03-01 17:45:00",
+            "2024-03-01,1,2024-03-31 23:59:59"
+        ]);
+        let expected_errors = vec![
+            ("PROJECT name FILTER day = '2024-01-01'", "Cannot compare date day with text '2024-01-01', write the value as DATE '2024-01-01'"),
+            ("PROJECT DATE_TRUNC('month', name)", "Function DATE_TRUNC expects date or timestamp as argument 2, found text"),
+            ("PROJECT DATE_DIFF('fortnight', day, started)", "Unknown unit 'fortnight', expected one of year, quarter, month, week, day, hour, minute, second")
+        ];
+        for (query, expected_error) in expected_errors.into_iter() {
+            match execute_on_database(&Query::parse(query).unwrap(), &database) {
+                Err(e) => assert_eq!(e.to_string(), expected_error, "{}", query),
+                Ok(_) => panic!("Error expected: {}", query)
+            }
+        }
+    }
+
+    #[test]
+    fn should_filter_by_comparing_expressions() {
+        let table = load_cities_table().unwrap();
+        let expected_results = vec![
+            ("population > area * 4000", vec!["Barcelona", "Berlin", "Madrid"]),
+            ("LOWER(country) = 'spain'", vec!["Barcelona", "Madrid"]),
+            ("UPPER(city) || country = 'ROMEItaly' OR 1500000 > population", vec!["Munich", "Rome"]),
+            ("(population + 1000000) / area < 4000 AND NOT city = 'Rome'", vec!["Hamburg"]),
+            ("area = NULL OR NOT area != NULL", vec![])
+        ];
+        assert_same_results_with_and_without_index(&table, "PROJECT city FILTER {} ORDER BY city", expected_results);
+    }
+
+    #[test]
+    fn should_compare_expressions_of_aggregates_and_joined_columns() {
+        let cities = load_cities_table().unwrap();
+        let countries = load_countries_table().unwrap();
+        let database = database_of(vec![("cities", &cities), ("countries", &countries)], true);
+        let query = Query::parse("PROJECT country, SUM(population), COUNT(*) GROUP BY country HAVING SUM(population) / COUNT(*) > 2400000").unwrap();
+        let result_set = execute_on_database(&query, &database_of(vec![("cities", &cities)], true)).unwrap();
+        assert_eq!(all_column_values(&result_set), vec!["Spain,4886469,2", "Italy,2873000,1"]);
+        let query = Query::parse("PROJECT city FROM cities JOIN countries ON cities.country = countries.country FILTER city = capital").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(all_column_values(&result_set), vec!["Berlin", "Madrid"]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn should_apply_string_functions() {
+        let table = load_cities_table().unwrap();
+        let database = indexed_database_of(&table);
+        let query = Query::parse("PROJECT UPPER(SUBSTR(city, 1, 3)), LENGTH(city), LPAD(CONCAT(population), 8, '0'), REPLACE(country, 'a', 'A'), \
+            SPLIT_PART(city || '-' || country, '-', 2) FILTER STARTS_WITH(city, 'B') ORDER BY city").unwrap();
+        let result_set = execute_on_database(&query, &database).unwrap();
+        assert_eq!(all_column_values(&result_set), vec!["BAR,9,01620343,SpAin,Spain", "BER,6,03644826,GermAny,Germany"]);
+        let expected_results = vec![
+            ("NOT STARTS_WITH(LOWER(city), 'b') AND LENGTH(TRIM(country)) = 5", vec!["Madrid", "Rome"]),
+            ("RPAD(city, 3) = 'Ham' OR CONCAT(area, '!') = '!'", vec!["Hamburg", "Munich"]),
+            ("STARTS_WITH(city, 'M') = 1", vec!["Madrid", "Munich"])
+        ];
+        assert_same_results_with_and_without_index(&table, "PROJECT city FILTER {} ORDER BY city", expected_results);
+    }
+
+    #[test]
+    fn should_check_types_of_function_arguments_before_execution() {
+        let cities = load_cities_table().unwrap();
+        let countries = load_countries_table().unwrap();
+        let database = database_of(vec![("cities", &cities), ("countries", &countries)], true);
+        let expected_errors = vec![
+            ("PROJECT UPPER(population) FROM cities FILTER city = 'Nowhere'", "Function UPPER expects text as argument 1, found integer"),
+            ("PROJECT city FROM cities FILTER STARTS_WITH(city, 'B') OR SUBSTR(city, area) = 'x'", "Function SUBSTR expects integer as argument 2, found float"),
+            ("PROJECT city FROM cities ORDER BY LENGTH(population / 2)", "Function LENGTH expects text as argument 1, found integer"),
+            ("PROJECT city FROM cities JOIN countries ON cities.country = countries.country FILTER LOWER(cities.population) = 'x'",
+             "Function LOWER expects text as argument 1, found integer"),
+            ("PROJECT LPAD(population, 9, '0') FROM cities", "Function LPAD expects text as argument 1, found integer"),
+            ("PROJECT city FROM cities JOIN countries ON cities.country = countries.country FILTER ROUND(countries.capital) = 1",
+             "Function ROUND expects numeric as argument 1, found text")
+        ];
+        for (query, expected_error) in expected_errors.into_iter() {
+            match execute_on_database(&Query::parse(query).unwrap(), &database) {
+                Err(e) => assert_eq!(e.to_string(), expected_error, "{}", query),
+                Ok(_) => panic!("Error expected: {}", query)
+            }
+        }
+        let query = Query::parse("PROJECT LPAD(population || '', 10) FROM cities FILTER city = 'Rome'").unwrap();
+        assert_eq!(all_column_values(&execute_on_database(&query, &database).unwrap()), vec!["   2873000"]);
+        let query = Query::parse("PROJECT LPAD(CONCAT(population), 9, '0'), LENGTH(area || '') FROM cities FILTER city IN ('Rome', 'Hamburg') ORDER BY city").unwrap();
+        assert_eq!(all_column_values(&execute_on_database(&query, &database).unwrap()), vec!["001841179,5", "002873000,6"]);
+    }
+}
@@ -1,6 +1,8 @@
 use anyhow::{anyhow, Result, Error};
 use std::collections::HashMap;
+use std::fmt;
 use csv;
+use crate::date_time;
 use crate::value::Value;
 
 #[derive(Debug, PartialEq)]
@@ -20,6 +22,7 @@ pub(crate) struct TableIndices<'a> {
     pub(crate) column_indices: HashMap<String, Index<'a>>
 }
 
+// NULL values are ordered before all other values, so they always occupy the beginning of the index
 #[derive(Debug, PartialEq)]
 pub(crate) struct Index<'a> {
     pub(crate) column_name: String,
@@ -38,19 +41,48 @@ pub(crate) struct Column {
     pub(crate) column_type: ColumnType
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub(crate) enum ColumnType {
     Integer,
+    Float,
+    Date,
+    Timestamp,
     Text
 }
 
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ColumnType::Integer => "integer",
+            ColumnType::Float => "float",
+            ColumnType::Date => "date",
+            ColumnType::Timestamp => "timestamp",
+            ColumnType::Text => "text"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct LoadOptions {
+    pub null_values: Vec<String>
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions {
+            null_values: vec!["".to_string()]
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub(crate) struct Row {
     pub fields: Vec<Value>
 }
 
 impl TableIndices<'_> {
-    pub fn build_for(table: &Table) -> Result<TableIndices, Error> {
+    pub fn build_for(table: &Table) -> Result<TableIndices<'_>, Error> {
         let mut column_indices: HashMap<String, Index> = HashMap::new();
         for (column_index, column) in table.columns.iter().enumerate() {
             let column_name = column.name.to_string();
@@ -77,28 +109,36 @@ impl TableIndices<'_> {
 impl Table {
 
     pub fn build_indices<'a>(&'a self) -> Result<IndexedTable<'a>, Error> {
-        let indices = TableIndices::build_for(&self)?;
+        let indices = TableIndices::build_for(self)?;
         Ok(IndexedTable {
-            underlying: &self,
+            underlying: self,
             indices
         })
     }
 
     pub fn load_from<R: std::io::Read>(reader: &mut csv::Reader<R>) -> Result<Table, Error> {
-        let rows = Table::parse_rows(reader)?;
-        let columns = Table::parse_columns(reader, &rows)?;
+        Table::load_with_options_from(reader, &LoadOptions::default())
+    }
+
+    pub fn load_with_options_from<R: std::io::Read>(reader: &mut csv::Reader<R>, options: &LoadOptions) -> Result<Table, Error> {
+        let mut rows = Table::parse_rows(reader, options)?;
+        let columns = Table::parse_columns(reader, &mut rows)?;
         Ok(Table {
             columns,
             rows
         })
     }
 
-    fn parse_rows<R: std::io::Read>(reader: &mut csv::Reader<R>) -> Result<Vec<Row>, Error> {
+    fn parse_rows<R: std::io::Read>(reader: &mut csv::Reader<R>, options: &LoadOptions) -> Result<Vec<Row>, Error> {
         let mut rows: Vec<Row> = Vec::new();
         for record in reader.records() {
             let mut fields: Vec<Value> = Vec::new();
             for column in record?.into_iter() {
-                let field = Value::parse_value(column.to_string())?;
+                let field = if options.null_values.iter().any(|null_value| null_value == column) {
+                    Value::Null
+                } else {
+                    Value::parse_value(column.to_string())?
+                };
                 fields.push(field);
             }
             rows.push(Row {
@@ -108,24 +148,43 @@ impl Table {
         Ok(rows)
     }
 
-    fn parse_columns<R: std::io::Read>(reader: &mut csv::Reader<R>, rows: &Vec<Row>) -> Result<Vec<Column>, Error> {
+    // Dates and timestamps are only recognized when all the values of the column are dates or timestamps,
+    // the text of the cells of such a column is then replaced by the parsed values
+    fn parse_columns<R: std::io::Read>(reader: &mut csv::Reader<R>, rows: &mut [Row]) -> Result<Vec<Column>, Error> {
         let headers: Vec<String> = reader.headers()?.into_iter().map(|header| header.to_string()).collect();
         let mut columns: Vec<Column> = Vec::new();
         for (index, header) in headers.into_iter().enumerate() {
             let mut column_values: Vec<&Value> = Vec::new();
             for row in rows.iter() {
                 let row_field = row.fields.get(index).ok_or_else(|| anyhow!("Row {:?} does not have column {:?}", &row, &header))?;
-                column_values.push(row_field);
+                if !row_field.is_null() {
+                    column_values.push(row_field);
+                }
             }
-            let is_integer_column = column_values.into_iter().all(|field| match field {
-                Value::Integer(_) => true,
-                Value::Text(_) => false
-            });
-            let column_type = if is_integer_column {
+            let are_all_texts_parsed_as = |column_type: &ColumnType| column_values.iter()
+                .all(|field| matches!(field, Value::Text(text) if parse_temporal(text, column_type).is_some()));
+            let column_type = if column_values.is_empty() {
+                ColumnType::Text
+            } else if column_values.iter().all(|field| matches!(field, Value::Integer(_))) {
                 ColumnType::Integer
+            } else if column_values.iter().all(|field| field.is_numeric()) {
+                ColumnType::Float
+            } else if are_all_texts_parsed_as(&ColumnType::Date) {
+                ColumnType::Date
+            } else if are_all_texts_parsed_as(&ColumnType::Timestamp) {
+                ColumnType::Timestamp
             } else {
                 ColumnType::Text
             };
+            for row in rows.iter_mut() {
+                let parsed = match &row.fields[index] {
+                    Value::Text(text) => parse_temporal(text, &column_type),
+                    _ => None
+                };
+                if let Some(parsed) = parsed {
+                    row.fields[index] = parsed;
+                }
+            }
             let column = Column {
                 name: header,
                 column_type
@@ -137,7 +196,7 @@ impl Table {
 
     pub fn find_column_position(&self, column_name: &str) -> Result<usize, Error> {
         self.columns.iter()
-            .position(|column| column.name == column_name.to_string())
+            .position(|column| column.name == column_name)
             .ok_or_else(|| anyhow!("Cannot find column {}, it does not exist in the table, existing columns {}",
                 column_name,
                 self.column_names().join(", "))
@@ -149,6 +208,15 @@ impl Table {
     }
 }
 
+// A date in a timestamp column is the midnight of the day
+fn parse_temporal(text: &str, column_type: &ColumnType) -> Option<Value> {
+    match column_type {
+        ColumnType::Date => date_time::parse_date(text).map(Value::Date),
+        ColumnType::Timestamp => date_time::parse_timestamp_or_date(text).map(Value::Timestamp),
+        _ => None
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -236,4 +304,80 @@ ccc,2,11"#;
             }
         })
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn should_infer_float_column_type_for_columns_mixing_integers_and_floats() {
+        let input = r#"column1,column2,column3
+a,891.8,-5
+b,105,3
+c,-0.5,7"#;
+        let mut reader = ReaderBuilder::new().from_reader(Cursor::new(input));
+        let table = Table::load_from(&mut reader).unwrap();
+        let column_types: Vec<&ColumnType> = table.columns.iter().map(|column| &column.column_type).collect();
+        assert_eq!(column_types, vec![&ColumnType::Text, &ColumnType::Float, &ColumnType::Integer]);
+        assert_eq!(table.rows[0].fields, vec![Value::Text("a".to_string()), Value::Float(891.8), Value::Integer(-5)]);
+    }
+
+    #[test]
+    fn should_infer_date_and_timestamp_column_types() {
+        let input = r#"column1,column2,column3,column4
+2024-01-31,2024-01-31T10:00:00,2024-01-31,2024-01-31
+2024-02-29,2024-02-29 23:59:59.5,2024-02-29 12:00,
+2023-12-01,2023-12-01T00:00:00Z,2023-12-01,2023-13-01"#;
+        let mut reader = ReaderBuilder::new().from_reader(Cursor::new(input));
+        let table = Table::load_from(&mut reader).unwrap();
+        let column_types: Vec<&ColumnType> = table.columns.iter().map(|column| &column.column_type).collect();
+        assert_eq!(column_types, vec![&ColumnType::Date, &ColumnType::Timestamp, &ColumnType::Timestamp, &ColumnType::Text]);
+        let formatted_fields: Vec<String> = table.rows[1].fields.iter().map(|field| field.to_string()).collect();
+        assert_eq!(formatted_fields, vec!["2024-02-29", "2024-02-29 23:59:59.500", "2024-02-29 12:00:00", "NULL"]);
+        assert_eq!(table.rows[0].fields[2].to_string(), "2024-01-31 00:00:00");
+    }
+
+    #[test]
+    fn should_keep_dates_as_text_in_text_columns() {
+        let input = r#"code,moment
+2024-01-31,2024-01-31T10:00:00Z
+X-1,soon"#;
+        let mut reader = ReaderBuilder::new().from_reader(Cursor::new(input));
+        let table = Table::load_from(&mut reader).unwrap();
+        let column_types: Vec<&ColumnType> = table.columns.iter().map(|column| &column.column_type).collect();
+        assert_eq!(column_types, vec![&ColumnType::Text, &ColumnType::Text]);
+        assert_eq!(table.rows[0].fields, vec![Value::Text("2024-01-31".to_string()), Value::Text("2024-01-31T10:00:00Z".to_string())]);
+    }
+
+    #[test]
+    fn should_load_empty_cells_and_configured_sentinels_as_nulls() {
+        let input = r#"column1,column2,column3
+a,,NA
+b,3,null
+,NA,"#;
+        let mut reader = ReaderBuilder::new().from_reader(Cursor::new(input));
+        let options = LoadOptions {
+            null_values: vec!["".to_string(), "NA".to_string(), "null".to_string()]
+        };
+        let table = Table::load_with_options_from(&mut reader, &options).unwrap();
+        let column_types: Vec<&ColumnType> = table.columns.iter().map(|column| &column.column_type).collect();
+        assert_eq!(column_types, vec![&ColumnType::Text, &ColumnType::Integer, &ColumnType::Text]);
+        assert_eq!(table.rows.iter().map(|row| &row.fields).collect::<Vec<&Vec<Value>>>(), vec![
+            &vec![Value::Text("a".to_string()), Value::Null, Value::Null],
+            &vec![Value::Text("b".to_string()), Value::Integer(3), Value::Null],
+            &vec![Value::Null, Value::Null, Value::Null]
+        ]);
+    }
+
+    #[test]
+    fn should_keep_nulls_at_the_beginning_of_the_index() {
+        let input = r#"column1,column2
+3,a
+-1,b
+,c
+2,d"#;
+        let mut reader = ReaderBuilder::new().from_reader(Cursor::new(input));
+        let table = Table::load_from(&mut reader).unwrap();
+        let indices = TableIndices::build_for(&table).unwrap();
+        let row_indices: Vec<usize> = indices.column_indices["column1"].sorted_column_values.iter()
+            .map(|value_in_row| value_in_row.row_index)
+            .collect();
+        assert_eq!(row_indices, vec![2, 1, 3, 0]);
+    }
+}
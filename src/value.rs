@@ -1,29 +1,60 @@
 use std::cmp::Ordering;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use anyhow::anyhow;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+use crate::date_time::{self, Interval};
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, Clone)]
 pub enum Value {
-    Integer(u64),
+    Null,
+    Integer(i64),
+    Float(f64),
+    Date(NaiveDate),
+    Timestamp(NaiveDateTime),
+    Interval(Interval),
     Text(String)
 }
 
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            Value::Null =>
+                write!(f, "NULL"),
             Value::Integer(value) =>
                 write!(f, "{}", value),
+            Value::Float(value) =>
+                write!(f, "{}", value),
+            Value::Date(value) =>
+                write!(f, "{}", value.format("%Y-%m-%d")),
+            Value::Timestamp(value) =>
+                write!(f, "{}", date_time::format_timestamp(value)),
+            Value::Interval(value) =>
+                write!(f, "{}", value),
             Value::Text(value) =>
                 write!(f, "{}", value)
         }
     }
 }
 
+// Integers and floats are compared by their numeric values, NaN is considered to be
+// equal to itself and greater than any other number. NULL is ordered before all other values
+// (and is equal to itself, which is needed for sorting, SQL comparison semantics for NULL are
+// implemented by the query engine), numbers are ordered before dates and timestamps, which are
+// ordered before intervals and text. A date is equal to the timestamp of its midnight.
 impl Ord for Value {
     fn cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
             (Value::Integer(x), Value::Integer(y)) => x.cmp(y),
+            (Value::Float(x), Value::Float(y)) => compare_floats(*x, *y),
+            (Value::Integer(x), Value::Float(y)) => compare_integer_to_float(*x, *y),
+            (Value::Float(x), Value::Integer(y)) => compare_integer_to_float(*y, *x).reverse(),
+            (Value::Interval(x), Value::Interval(y)) => x.length_in_microseconds().cmp(&y.length_in_microseconds()),
             (Value::Text(x), Value::Text(y)) => x.cmp(y),
-            (x, y) => format!("{:?}", x).cmp(&format!("{:?}", y))
+            (x, y) => match (x.as_timestamp(), y.as_timestamp()) {
+                (Some(x), Some(y)) => x.cmp(&y),
+                _ => x.type_rank().cmp(&y.type_rank())
+            }
         }
     }
 }
@@ -34,12 +65,353 @@ impl PartialOrd for Value {
     }
 }
 
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Value {}
+
+// Has to be consistent with equality, so a float with an integral value is hashed as an integer
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.type_rank().hash(state);
+        match self {
+            Value::Null => {},
+            Value::Integer(value) => value.hash(state),
+            Value::Float(value) => {
+                let is_integral = value.fract() == 0.0 && Value::Float(*value) == Value::Integer(*value as i64);
+                if is_integral {
+                    (*value as i64).hash(state)
+                } else if value.is_nan() {
+                    f64::NAN.to_bits().hash(state)
+                } else {
+                    value.to_bits().hash(state)
+                }
+            },
+            Value::Date(_) | Value::Timestamp(_) => self.as_timestamp().hash(state),
+            Value::Interval(value) => value.length_in_microseconds().hash(state),
+            Value::Text(value) => value.hash(state)
+        }
+    }
+}
+
+fn compare_floats(x: f64, y: f64) -> Ordering {
+    x.partial_cmp(&y).unwrap_or_else(|| x.is_nan().cmp(&y.is_nan()))
+}
+
+// Converting the integer to a float might lose precision, so the integer part of the float
+// is compared to the integer instead and only then the fractional part is taken into account
+fn compare_integer_to_float(x: i64, y: f64) -> Ordering {
+    const TWO_TO_63: f64 = 9223372036854775808.0;
+    if y.is_nan() || y >= TWO_TO_63 {
+        Ordering::Less
+    } else if y < -TWO_TO_63 {
+        Ordering::Greater
+    } else {
+        let integer_part = y.trunc();
+        match x.cmp(&(integer_part as i64)) {
+            Ordering::Equal => compare_floats(0.0, y - integer_part),
+            ordering => ordering
+        }
+    }
+}
+
 impl Value {
     pub(crate) fn parse_value(value: String) -> anyhow::Result<Value, anyhow::Error> {
-        if value.chars().all(|char| char.is_digit(10)) {
-            Ok(Value::Integer(value.parse()?))
+        if let Ok(integer) = value.parse::<i64>() {
+            Ok(Value::Integer(integer))
+        } else if Value::is_float_literal(&value) {
+            Ok(Value::Float(value.parse()?))
         } else {
             Ok(Value::Text(value))
         }
     }
+
+    // Only plain decimal notation is accepted, so that texts like "inf" or "NaN" stay texts
+    fn is_float_literal(value: &str) -> bool {
+        value.chars().any(|char| char.is_ascii_digit())
+            && value.chars().all(|char| char.is_ascii_digit() || ['.', '-', '+', 'e', 'E'].contains(&char))
+            && value.parse::<f64>().is_ok()
+    }
+
+    pub(crate) fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    pub(crate) fn is_numeric(&self) -> bool {
+        matches!(self, Value::Integer(_) | Value::Float(_))
+    }
+
+    pub(crate) fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Integer(value) => Some(*value as f64),
+            Value::Float(value) => Some(*value),
+            _ => None
+        }
+    }
+
+    // A date is taken as the timestamp of its midnight
+    pub(crate) fn as_timestamp(&self) -> Option<NaiveDateTime> {
+        match self {
+            Value::Date(value) => Some(value.and_time(NaiveTime::MIN)),
+            Value::Timestamp(value) => Some(*value),
+            _ => None
+        }
+    }
+
+    pub(crate) fn add(&self, other: &Value) -> anyhow::Result<Value, anyhow::Error> {
+        match (self, other) {
+            (Value::Integer(_) | Value::Interval(_), Value::Date(_) | Value::Timestamp(_)) => other.add(self),
+            (Value::Date(_) | Value::Timestamp(_) | Value::Interval(_), _) => self.apply_date_arithmetic(other, ("add", "adding"), false),
+            _ => self.apply_arithmetic(other, ("add", "adding"), i64::checked_add, |x, y| x + y)
+        }
+    }
+
+    pub(crate) fn subtract(&self, other: &Value) -> anyhow::Result<Value, anyhow::Error> {
+        match self {
+            Value::Date(_) | Value::Timestamp(_) | Value::Interval(_) => self.apply_date_arithmetic(other, ("subtract", "subtracting"), true),
+            _ => self.apply_arithmetic(other, ("subtract", "subtracting"), i64::checked_sub, |x, y| x - y)
+        }
+    }
+
+    pub(crate) fn multiply(&self, other: &Value) -> anyhow::Result<Value, anyhow::Error> {
+        self.apply_arithmetic(other, ("multiply", "multiplying"), i64::checked_mul, |x, y| x * y)
+    }
+
+    // Dividing integers truncates the result like in SQL
+    pub(crate) fn divide(&self, other: &Value) -> anyhow::Result<Value, anyhow::Error> {
+        if other.as_float() == Some(0.0) {
+            return Err(arithmetic_error(format!("Division by zero when dividing {} by {}", self, other)));
+        }
+        self.apply_arithmetic(other, ("divide", "dividing"), i64::checked_div, |x, y| x / y)
+    }
+
+    pub(crate) fn negate(&self) -> anyhow::Result<Value, anyhow::Error> {
+        match self {
+            Value::Integer(value) => value.checked_neg()
+                .map(Value::Integer)
+                .ok_or_else(|| arithmetic_error(format!("Integer overflow when negating {}", value))),
+            Value::Float(value) => Ok(Value::Float(-value)),
+            Value::Interval(value) => value.checked_neg()
+                .map(Value::Interval)
+                .ok_or_else(|| arithmetic_error(format!("Overflow when negating {}", value))),
+            value => Err(anyhow!("Cannot negate non-numeric value {}", value))
+        }
+    }
+
+    // Integers stay integers, an operation involving a float produces a float, an integer result
+    // which overflows or an infinite float result of finite operands is an error
+    fn apply_arithmetic(&self, other: &Value, (verb, gerund): (&str, &str), integer_operation: fn(i64, i64) -> Option<i64>, float_operation: fn(f64, f64) -> f64) -> anyhow::Result<Value, anyhow::Error> {
+        match (self, other) {
+            (Value::Integer(x), Value::Integer(y)) =>
+                integer_operation(*x, *y)
+                    .map(Value::Integer)
+                    .ok_or_else(|| arithmetic_error(format!("Integer overflow when {} {} and {}", gerund, x, y))),
+            (x, y) => match (x.as_float(), y.as_float()) {
+                (Some(x), Some(y)) => match float_operation(x, y) {
+                    result if result.is_infinite() && x.is_finite() && y.is_finite() =>
+                        Err(arithmetic_error(format!("Overflow when {} {} and {}", gerund, x, y))),
+                    result => Ok(Value::Float(result))
+                },
+                _ => Err(anyhow!("Cannot {} non-numeric values {} and {}", verb, x, y))
+            }
+        }
+    }
+
+    // Days (as integers) and intervals can be added to and subtracted from dates and timestamps,
+    // subtracting dates produces the number of days and subtracting timestamps produces an interval.
+    // A date stays a date unless the time of an interval is added to it.
+    fn apply_date_arithmetic(&self, other: &Value, (verb, gerund): (&str, &str), subtract: bool) -> anyhow::Result<Value, anyhow::Error> {
+        let out_of_range = || arithmetic_error(format!("Date out of range when {} {} and {}", gerund, self, other));
+        let signed = |amount: i64| if subtract { amount.checked_neg() } else { Some(amount) };
+        match (self, other) {
+            (Value::Date(date), Value::Integer(days)) => signed(*days)
+                .and_then(TimeDelta::try_days)
+                .and_then(|days| date.checked_add_signed(days))
+                .map(Value::Date)
+                .ok_or_else(out_of_range),
+            (Value::Date(_) | Value::Timestamp(_), Value::Interval(interval)) => {
+                let interval = if subtract { interval.checked_neg() } else { Some(*interval) };
+                let timestamp = interval.as_ref()
+                    .and_then(|interval| date_time::add_interval(self.as_timestamp()?, interval))
+                    .ok_or_else(out_of_range)?;
+                match (self, interval) {
+                    (Value::Date(_), Some(interval)) if !interval.has_time() => Ok(Value::Date(timestamp.date())),
+                    _ => Ok(Value::Timestamp(timestamp))
+                }
+            },
+            (Value::Date(x), Value::Date(y)) if subtract =>
+                Ok(Value::Integer(x.signed_duration_since(*y).num_days())),
+            (Value::Date(_) | Value::Timestamp(_), Value::Date(_) | Value::Timestamp(_)) if subtract =>
+                self.as_timestamp().zip(other.as_timestamp())
+                    .and_then(|(x, y)| date_time::interval_between(y, x))
+                    .map(Value::Interval)
+                    .ok_or_else(out_of_range),
+            (Value::Interval(x), Value::Interval(y)) =>
+                (if subtract { y.checked_neg() } else { Some(*y) })
+                    .and_then(|y| x.checked_add(&y))
+                    .map(Value::Interval)
+                    .ok_or_else(|| arithmetic_error(format!("Overflow when {} {} and {}", gerund, x, y))),
+            (x, y) => Err(anyhow!("Cannot {} {} and {}", verb, x, y))
+        }
+    }
+
+    fn type_rank(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Integer(_) | Value::Float(_) => 1,
+            Value::Date(_) | Value::Timestamp(_) => 2,
+            Value::Interval(_) => 3,
+            Value::Text(_) => 4
+        }
+    }
+}
+
+// An overflow, a division by zero or an argument outside of the domain of a function, which
+// depending on the execution options either fails the query or produces NULL
+#[derive(Debug, PartialEq)]
+pub struct ArithmeticError {
+    pub message: String
+}
+
+impl fmt::Display for ArithmeticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ArithmeticError {}
+
+pub(crate) fn arithmetic_error(message: String) -> anyhow::Error {
+    anyhow::Error::new(ArithmeticError { message })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_parse_signed_integers_and_floats() {
+        assert_eq!(Value::parse_value("42".to_string()).unwrap(), Value::Integer(42));
+        assert_eq!(Value::parse_value("-42".to_string()).unwrap(), Value::Integer(-42));
+        assert_eq!(Value::parse_value("891.8".to_string()).unwrap(), Value::Float(891.8));
+        assert_eq!(Value::parse_value("-0.5".to_string()).unwrap(), Value::Float(-0.5));
+        assert_eq!(Value::parse_value("1e3".to_string()).unwrap(), Value::Float(1000.0));
+        assert_eq!(Value::parse_value("NaN".to_string()).unwrap(), Value::Text("NaN".to_string()));
+        assert_eq!(Value::parse_value("1.2.3".to_string()).unwrap(), Value::Text("1.2.3".to_string()));
+    }
+
+    #[test]
+    fn should_compare_integers_and_floats_by_numeric_value() {
+        assert!(Value::Integer(2) < Value::Float(2.5));
+        assert!(Value::Float(2.5) < Value::Integer(3));
+        assert!(Value::Integer(-3) < Value::Float(-2.5));
+        assert_eq!(Value::Integer(2), Value::Float(2.0));
+        assert!(Value::Integer(i64::MAX) < Value::Float(9223372036854775808.0));
+        assert!(Value::Integer(i64::MIN) > Value::Float(f64::NEG_INFINITY));
+    }
+
+    #[test]
+    fn should_order_nan_after_all_other_numbers() {
+        assert_eq!(Value::Float(f64::NAN), Value::Float(f64::NAN));
+        assert!(Value::Float(f64::INFINITY) < Value::Float(f64::NAN));
+        assert!(Value::Integer(i64::MAX) < Value::Float(f64::NAN));
+        assert!(Value::Float(f64::NAN) < Value::Text("a".to_string()));
+    }
+
+    #[test]
+    fn should_order_null_before_all_other_values() {
+        assert_eq!(Value::Null, Value::Null);
+        assert!(Value::Null < Value::Integer(i64::MIN));
+        assert!(Value::Null < Value::Float(f64::NEG_INFINITY));
+        assert!(Value::Null < Value::Text("".to_string()));
+    }
+
+    fn hash_of(value: &Value) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn should_hash_equal_integers_and_floats_equally() {
+        assert_eq!(hash_of(&Value::Integer(3)), hash_of(&Value::Float(3.0)));
+        assert_eq!(hash_of(&Value::Integer(0)), hash_of(&Value::Float(-0.0)));
+        assert_eq!(hash_of(&Value::Float(f64::NAN)), hash_of(&Value::Float(-f64::NAN)));
+        assert_ne!(hash_of(&Value::Integer(3)), hash_of(&Value::Float(3.5)));
+    }
+
+    #[test]
+    fn should_add_numeric_values() {
+        assert_eq!(Value::Integer(2).add(&Value::Integer(3)).unwrap(), Value::Integer(5));
+        assert_eq!(Value::Integer(2).add(&Value::Float(0.5)).unwrap(), Value::Float(2.5));
+        assert!(Value::Integer(i64::MAX).add(&Value::Integer(1)).is_err());
+        assert!(Value::Integer(1).add(&Value::Text("a".to_string())).is_err());
+    }
+
+    fn parsed(value: &str) -> Value {
+        date_time::parse_date(value).map(Value::Date)
+            .or_else(|| date_time::parse_timestamp(value).map(Value::Timestamp))
+            .unwrap()
+    }
+
+    fn interval(value: &str) -> Value {
+        Value::Interval(Interval::parse(value).unwrap())
+    }
+
+    #[test]
+    fn should_compare_dates_and_timestamps_by_time() {
+        assert!(matches!(parsed("2024-01-31"), Value::Date(_)));
+        assert!(matches!(parsed("2024-01-31T10:00:00"), Value::Timestamp(_)));
+        assert_eq!(parsed("2024-01-31"), parsed("2024-01-31 00:00:00"));
+        assert_eq!(hash_of(&parsed("2024-01-31")), hash_of(&parsed("2024-01-31 00:00:00")));
+        assert!(parsed("2024-01-31") < parsed("2024-01-31 00:00:01"));
+        assert!(parsed("2024-01-31 23:59:59") < parsed("2024-02-01"));
+        assert!(Value::Integer(i64::MAX) < parsed("0001-01-01"));
+        assert!(parsed("9999-12-31") < interval("1 day"));
+        assert!(interval("1 day") < Value::Text("".to_string()));
+        assert_eq!(interval("1 month"), interval("30 days"));
+        assert!(interval("1 day") < interval("25 hours"));
+    }
+
+    #[test]
+    fn should_add_and_subtract_days_and_intervals() {
+        let expected_results = vec![
+            (parsed("2024-02-28").add(&Value::Integer(2)), parsed("2024-03-01")),
+            (Value::Integer(2).add(&parsed("2024-02-28")), parsed("2024-03-01")),
+            (parsed("2024-03-01").subtract(&Value::Integer(1)), parsed("2024-02-29")),
+            (parsed("2024-01-31").add(&interval("1 month")), parsed("2024-02-29")),
+            (parsed("2024-01-31").add(&interval("36 hours")), parsed("2024-02-01 12:00:00")),
+            (interval("1 hour").add(&parsed("2024-01-31 23:30:00")), parsed("2024-02-01 00:30:00")),
+            (parsed("2024-01-31 10:00:00").subtract(&interval("1 year")), parsed("2023-01-31 10:00:00")),
+            (parsed("2024-03-01").subtract(&parsed("2024-02-01")), Value::Integer(29)),
+            (parsed("2024-03-01 06:00:00").subtract(&parsed("2024-02-28")), interval("2 days 6 hours")),
+            (interval("1 day").subtract(&interval("2 hours")), interval("22 hours")),
+            (interval("1 day").negate(), interval("-1 day"))
+        ];
+        for (result, expected_result) in expected_results.into_iter() {
+            assert_eq!(result.unwrap(), expected_result);
+        }
+        assert_eq!(parsed("9999-12-31").add(&interval("1000000 years")).unwrap_err().to_string(),
+            "Date out of range when adding 9999-12-31 and 12000000 months");
+        assert!(parsed("2024-01-01").add(&Value::Integer(i64::MAX)).unwrap_err().is::<ArithmeticError>());
+        assert_eq!(parsed("2024-01-01").add(&parsed("2024-01-01")).unwrap_err().to_string(), "Cannot add 2024-01-01 and 2024-01-01");
+        assert_eq!(parsed("2024-01-01").multiply(&Value::Integer(2)).unwrap_err().to_string(), "Cannot multiply non-numeric values 2024-01-01 and 2");
+    }
+
+    #[test]
+    fn should_apply_arithmetic_to_numeric_values() {
+        assert_eq!(Value::Integer(2).subtract(&Value::Integer(3)).unwrap(), Value::Integer(-1));
+        assert_eq!(Value::Integer(2).multiply(&Value::Float(1.5)).unwrap(), Value::Float(3.0));
+        assert_eq!(Value::Integer(7).divide(&Value::Integer(2)).unwrap(), Value::Integer(3));
+        assert_eq!(Value::Float(7.0).divide(&Value::Integer(2)).unwrap(), Value::Float(3.5));
+        assert_eq!(Value::Integer(1).divide(&Value::Float(0.0)).unwrap_err().to_string(), "Division by zero when dividing 1 by 0");
+        assert_eq!(Value::Integer(i64::MIN).divide(&Value::Integer(-1)).unwrap_err().to_string(), "Integer overflow when dividing -9223372036854775808 and -1");
+        assert_eq!(Value::Text("a".to_string()).multiply(&Value::Integer(2)).unwrap_err().to_string(), "Cannot multiply non-numeric values a and 2");
+        assert!(Value::Float(f64::MAX).multiply(&Value::Integer(2)).unwrap_err().is::<ArithmeticError>());
+        assert!(Value::Integer(i64::MAX).add(&Value::Integer(1)).unwrap_err().is::<ArithmeticError>());
+        assert!(!Value::Text("a".to_string()).add(&Value::Integer(1)).unwrap_err().is::<ArithmeticError>());
+    }
 }